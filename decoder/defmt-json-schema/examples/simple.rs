@@ -1,6 +1,6 @@
 use std::fs;
 
-use defmt_json_schema::{v1, SchemaVersion};
+use defmt_json_schema::{v1, v2, SchemaVersion};
 
 fn main() {
     let s = fs::read_to_string("examples/simple.json").unwrap();
@@ -10,7 +10,7 @@ fn main() {
 
     match schema_version {
         v1::SCHEMA_VERSION => handle_v1(&data[1..]),
-        // v2::SCHEMA_VERSION => handle_v2(&data[1..]),
+        v2::SCHEMA_VERSION => handle_v2(&data[1..]),
         _ => unreachable!(),
     };
 }
@@ -24,3 +24,13 @@ fn handle_v1(data: &[&str]) {
         println!("{json_frame:?}");
     }
 }
+
+fn handle_v2(data: &[&str]) {
+    println!("Detected version \"2\" of JsonFrame!");
+    use v2::JsonFrame;
+
+    for &data in data.iter() {
+        let json_frame: JsonFrame = serde_json::from_str(data).unwrap();
+        println!("{json_frame:?}");
+    }
+}
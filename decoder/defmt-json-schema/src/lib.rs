@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use log::Level;
 use serde::{Deserialize, Serialize};
 
@@ -35,3 +37,247 @@ pub mod v1 {
         pub function: String,
     }
 }
+
+pub mod v2 {
+    use super::*;
+
+    pub use super::v1::{Location, ModulePath};
+
+    pub const SCHEMA_VERSION: SchemaVersion = SchemaVersion { schema_version: 2 };
+
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    pub struct JsonFrame {
+        pub data: String,
+        /// Format string of the log statement, without its structured fields
+        pub format: String,
+        /// Index of the format string in the `.defmt` section
+        pub index: u64,
+        /// Arguments of the format string
+        pub args: Vec<Value>,
+        /// Structured fields of the log statement, as in `info!("msg", name = value)`
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub fields: BTreeMap<String, Value>,
+        /// Unix timestamp in nanoseconds
+        pub host_timestamp: i64,
+        pub level: Option<Level>,
+        pub location: Location,
+        pub target_timestamp: String,
+        /// Estimated Unix timestamp in nanoseconds at which the frame was logged, from the
+        /// correlation of the timestamps of the target with the host clock
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub wall_clock: Option<i64>,
+        /// Name of the core that sent the frame, when decoding the logs of several firmware
+        /// images at once
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub core: Option<String>,
+    }
+
+    /// A decoded argument
+    ///
+    /// It is serialized as an object with its `type` and, unless it is a unit, its `value`; e.g.
+    /// `{"type":"uint","value":42}`.
+    #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+    #[serde(tag = "type", content = "value", rename_all = "snake_case")]
+    pub enum Value {
+        Bool(bool),
+        Uint(u128),
+        Int(i128),
+        /// A number, or, if it isn't finite, a string like `"inf"`, `"-inf"`, `"NaN"` or, with
+        /// the sign and the payload of the NaN, `"-NaN(0x1)"`
+        F32(#[serde(with = "super::float")] f32),
+        /// Like `F32`
+        F64(#[serde(with = "super::float")] f64),
+        Char(char),
+        Str(String),
+        /// Slice or array of bytes
+        Bytes(Vec<u8>),
+        /// Slice or array of `Format` values
+        List(Vec<Value>),
+        /// Struct or enum variant with named fields
+        Struct {
+            name: String,
+            fields: BTreeMap<String, Value>,
+        },
+        /// Tuple struct or tuple enum variant
+        Tuple {
+            name: String,
+            fields: Vec<Value>,
+        },
+        /// Unit struct or unit enum variant
+        Unit {
+            name: String,
+        },
+        /// Any other `Format` implementation, with its format string and arguments
+        Format {
+            format: String,
+            args: Vec<Value>,
+        },
+    }
+}
+
+pub use float::{non_finite_to_string, Float};
+
+/// (De)serialization of floats that aren't finite, which JSON numbers can't represent, as strings
+mod float {
+    use std::{fmt, marker::PhantomData, str::FromStr};
+
+    use serde::{de, Deserializer, Serialize, Serializer};
+
+    /// The floating-point types of [`v2::Value`](crate::v2::Value)
+    pub trait Float: Copy + Serialize + FromStr {
+        /// Width of the type in bits
+        const BITS: u32;
+        /// Width of the mantissa in bits, which holds the payload of a NaN
+        const MANTISSA_BITS: u32;
+
+        /// Returns the raw bits of the float.
+        fn bits(self) -> u64;
+        fn from_bits(bits: u64) -> Self;
+        fn from_f64(x: f64) -> Self;
+    }
+
+    impl Float for f32 {
+        const BITS: u32 = 32;
+        const MANTISSA_BITS: u32 = 23;
+
+        fn bits(self) -> u64 {
+            self.to_bits().into()
+        }
+
+        fn from_bits(bits: u64) -> Self {
+            f32::from_bits(bits as u32)
+        }
+
+        fn from_f64(x: f64) -> Self {
+            x as f32
+        }
+    }
+
+    impl Float for f64 {
+        const BITS: u32 = 64;
+        const MANTISSA_BITS: u32 = 52;
+
+        fn bits(self) -> u64 {
+            self.to_bits()
+        }
+
+        fn from_bits(bits: u64) -> Self {
+            f64::from_bits(bits)
+        }
+
+        fn from_f64(x: f64) -> Self {
+            x
+        }
+    }
+
+    fn sign_bit<F: Float>() -> u64 {
+        1 << (F::BITS - 1)
+    }
+
+    fn exponent_bits<F: Float>() -> u64 {
+        (sign_bit::<F>() - 1) & !payload_bits::<F>()
+    }
+
+    fn payload_bits<F: Float>() -> u64 {
+        (1 << F::MANTISSA_BITS) - 1
+    }
+
+    /// The payload of the default quiet NaN
+    fn quiet_nan<F: Float>() -> u64 {
+        1 << (F::MANTISSA_BITS - 1)
+    }
+
+    /// Returns `x` as a string like `"inf"`, `"-NaN"` or `"NaN(0x1)"`, if it isn't finite.
+    ///
+    /// `NaN` stands for the default quiet NaN; any other payload is shown as well.
+    pub fn non_finite_to_string<F: Float>(x: F) -> Option<String> {
+        let bits = x.bits();
+        if bits & exponent_bits::<F>() != exponent_bits::<F>() {
+            return None;
+        }
+
+        let sign = match bits & sign_bit::<F>() != 0 {
+            true => "-",
+            false => "",
+        };
+        let payload = bits & payload_bits::<F>();
+        Some(if payload == 0 {
+            format!("{sign}inf")
+        } else if payload == quiet_nan::<F>() {
+            format!("{sign}NaN")
+        } else {
+            format!("{sign}NaN({payload:#x})")
+        })
+    }
+
+    pub(crate) fn serialize<F: Float, S: Serializer>(
+        x: &F,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match non_finite_to_string(*x) {
+            Some(s) => serializer.serialize_str(&s),
+            None => x.serialize(serializer),
+        }
+    }
+
+    struct Visitor<F>(PhantomData<F>);
+
+    impl<'de, F: Float> de::Visitor<'de> for Visitor<F> {
+        type Value = F;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a float, or a string like \"inf\" or \"NaN\"")
+        }
+
+        fn visit_f64<E: de::Error>(self, x: f64) -> Result<F, E> {
+            Ok(F::from_f64(x))
+        }
+
+        fn visit_i64<E: de::Error>(self, x: i64) -> Result<F, E> {
+            Ok(F::from_f64(x as f64))
+        }
+
+        fn visit_u64<E: de::Error>(self, x: u64) -> Result<F, E> {
+            Ok(F::from_f64(x as f64))
+        }
+
+        fn visit_str<E: de::Error>(self, s: &str) -> Result<F, E> {
+            parse_non_finite(s).ok_or_else(|| E::invalid_value(de::Unexpected::Str(s), &self))
+        }
+
+        // with the `arbitrary_precision` feature of `serde_json`, numbers are maps with a single
+        // entry, which holds their digits
+        fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<F, A::Error> {
+            let digits = match map.next_entry::<String, String>()? {
+                Some((_, digits)) => digits,
+                None => return Err(de::Error::invalid_length(0, &self)),
+            };
+            digits
+                .parse()
+                .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(&digits), &self))
+        }
+    }
+
+    pub(crate) fn deserialize<'de, F: Float, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<F, D::Error> {
+        deserializer.deserialize_any(Visitor(PhantomData))
+    }
+
+    fn parse_non_finite<F: Float>(s: &str) -> Option<F> {
+        let (sign, rest) = match s.strip_prefix('-') {
+            Some(rest) => (sign_bit::<F>(), rest),
+            None => (0, s),
+        };
+        let payload = match rest {
+            "inf" => 0,
+            "NaN" => quiet_nan::<F>(),
+            _ => rest
+                .strip_prefix("NaN(0x")
+                .and_then(|rest| rest.strip_suffix(')'))
+                .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+                .filter(|payload| *payload != 0 && *payload & !payload_bits::<F>() == 0)?,
+        };
+        Some(F::from_bits(sign | exponent_bits::<F>() | payload))
+    }
+}
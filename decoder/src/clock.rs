@@ -0,0 +1,267 @@
+//! Correlation of the timestamps of the target with the clock of the host.
+
+use defmt_parser::TimePrecision;
+use serde::{Deserialize, Serialize};
+
+/// Largest relative drift that is plausible for the clock of a target.
+///
+/// A larger one rather means that the host received frames in bursts, e.g. from a buffer or a
+/// file, so the nominal length of a tick is used instead.
+const MAX_DRIFT: f64 = 0.05;
+
+/// The numeric value of the timestamp of a frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TargetTimestamp {
+    /// Value of the timestamp, in ticks of the clock of the target
+    pub ticks: u64,
+    /// Length of a tick in nanoseconds, if the timestamp format has a time display hint like
+    /// `:us` or `:ms`
+    pub nanos_per_tick: Option<u64>,
+    /// Width of the timestamp in bits, e.g. 32 for `{=u32:us}`; it wraps around after that
+    pub bits: u32,
+}
+
+impl TargetTimestamp {
+    pub(crate) fn nanos_per_tick(precision: &TimePrecision) -> u64 {
+        match precision {
+            TimePrecision::Micros => 1_000,
+            TimePrecision::Millis => 1_000_000,
+            TimePrecision::Seconds => 1_000_000_000,
+        }
+    }
+}
+
+/// Extends timestamps that wrap around, like `{=u32:us}` after about 71 minutes, to 64 bits.
+///
+/// Timestamps are expected to increase. If one goes back by more than half of its range, it
+/// wrapped around; if it goes back by less, the target likely restarted, and the extension starts
+/// over.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TimestampExtension {
+    /// Sum of the ranges of the timestamp for each wraparound so far
+    epoch: u64,
+    last_ticks: Option<u64>,
+}
+
+impl TimestampExtension {
+    /// Returns `timestamp`, extended to 64 bits.
+    pub(crate) fn extend(&mut self, timestamp: TargetTimestamp) -> TargetTimestamp {
+        if timestamp.bits >= u64::BITS {
+            return timestamp;
+        }
+        let range = 1 << timestamp.bits;
+        if let Some(last_ticks) = self.last_ticks {
+            if timestamp.ticks < last_ticks {
+                match last_ticks - timestamp.ticks > range / 2 {
+                    true => self.epoch = self.epoch.wrapping_add(range),
+                    false => self.epoch = 0,
+                }
+            }
+        }
+        self.last_ticks = Some(timestamp.ticks);
+
+        TargetTimestamp {
+            ticks: self.epoch.wrapping_add(timestamp.ticks),
+            bits: u64::BITS,
+            ..timestamp
+        }
+    }
+}
+
+/// Estimates when the frames of a target were logged, in the time of the host.
+///
+/// It fits a line through the timestamps of the frames and the times at which the host received
+/// them, with the least squares method. Its offset is when the clock of the target started, and
+/// its slope is the length of a tick, which differs from the nominal one by the drift of the
+/// clock of the target relative to the one of the host.
+///
+/// The time it takes a frame to reach the host is part of the offset, so the estimates are a bit
+/// late, but they don't jitter with the latency of the transport.
+#[derive(Clone, Debug, Default)]
+pub struct ClockCorrelation {
+    /// The first observation, relative to which the others are stored, to preserve precision
+    origin: Option<Observation>,
+    last_ticks: u64,
+    nanos_per_tick: Option<u64>,
+    count: f64,
+    mean_ticks: f64,
+    mean_nanos: f64,
+    /// Sum of the squared deviations of the ticks from their mean
+    var_ticks: f64,
+    /// Sum of the products of the deviations of the ticks and the host times from their means
+    covar: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Observation {
+    ticks: u64,
+    host_nanos: i64,
+}
+
+impl ClockCorrelation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that a frame with `timestamp` was received at `host_nanos`, in nanoseconds since
+    /// the Unix epoch.
+    ///
+    /// If the timestamp went backwards, the target likely restarted, and the correlation starts
+    /// over.
+    pub fn observe(&mut self, timestamp: TargetTimestamp, host_nanos: i64) {
+        let restarted = timestamp.ticks < self.last_ticks;
+        if self.origin.is_none() || restarted || timestamp.nanos_per_tick != self.nanos_per_tick {
+            *self = Self {
+                origin: Some(Observation {
+                    ticks: timestamp.ticks,
+                    host_nanos,
+                }),
+                nanos_per_tick: timestamp.nanos_per_tick,
+                ..Self::default()
+            };
+        }
+        let origin = self.origin.unwrap();
+        self.last_ticks = timestamp.ticks;
+
+        let ticks = (timestamp.ticks - origin.ticks) as f64;
+        let nanos = host_nanos.wrapping_sub(origin.host_nanos) as f64;
+        self.count += 1.0;
+        let delta_ticks = ticks - self.mean_ticks;
+        self.mean_ticks += delta_ticks / self.count;
+        self.mean_nanos += (nanos - self.mean_nanos) / self.count;
+        self.var_ticks += delta_ticks * (ticks - self.mean_ticks);
+        self.covar += delta_ticks * (nanos - self.mean_nanos);
+    }
+
+    /// Returns the fitted length of a tick in nanoseconds, if it is plausible.
+    fn fitted_slope(&self) -> Option<f64> {
+        if self.var_ticks <= 0.0 {
+            return None;
+        }
+        let slope = self.covar / self.var_ticks;
+        match self.nanos_per_tick {
+            Some(nominal) if (slope / nominal as f64 - 1.0).abs() > MAX_DRIFT => None,
+            _ => Some(slope),
+        }
+    }
+
+    /// Returns the estimated length of a tick in nanoseconds, or `None` if it is still unknown.
+    fn slope(&self) -> Option<f64> {
+        self.fitted_slope()
+            .or(self.nanos_per_tick.map(|nanos| nanos as f64))
+    }
+
+    /// Returns the estimated time, in nanoseconds since the Unix epoch, at which the target logged
+    /// a frame with `timestamp`.
+    ///
+    /// Returns `None` before the first observation, or, if the length of a tick is unknown,
+    /// before two observations with different timestamps.
+    pub fn estimate(&self, timestamp: TargetTimestamp) -> Option<i64> {
+        let origin = self.origin?;
+        if timestamp.nanos_per_tick != self.nanos_per_tick {
+            return None;
+        }
+        let ticks = timestamp.ticks as f64 - origin.ticks as f64;
+        let nanos = self.mean_nanos + self.slope()? * (ticks - self.mean_ticks);
+        Some(origin.host_nanos.saturating_add(nanos.round() as i64))
+    }
+
+    /// Returns the estimated drift of the clock of the target, in parts per million; positive if
+    /// it runs slow.
+    ///
+    /// Returns `None` if the length of a tick is unknown, before two observations with
+    /// different timestamps, or if the frames arrived in bursts.
+    pub fn drift(&self) -> Option<f64> {
+        let nominal = self.nanos_per_tick? as f64;
+        Some((self.fitted_slope()? / nominal - 1.0) * 1e6)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn us(ticks: u64) -> TargetTimestamp {
+        TargetTimestamp {
+            ticks,
+            nanos_per_tick: Some(1_000),
+            bits: 64,
+        }
+    }
+
+    #[test]
+    fn offset_and_drift() {
+        let mut clock = ClockCorrelation::new();
+        assert_eq!(clock.estimate(us(0)), None);
+
+        // the target starts 5 s after the epoch, and its clock runs 100 ppm fast; a tick of the
+        // target takes 999.9 ns of the host
+        for ticks in (0..10_000_000).step_by(1_000_000) {
+            let host_nanos = 5_000_000_000 + ticks as i64 * 9_999 / 10;
+            clock.observe(us(ticks), host_nanos);
+        }
+
+        assert_eq!(clock.estimate(us(20_000_000)), Some(24_998_000_000));
+        let drift = clock.drift().unwrap();
+        assert!((drift + 100.0).abs() < 1e-6, "{drift}");
+    }
+
+    #[test]
+    fn single_observation() {
+        let mut clock = ClockCorrelation::new();
+        clock.observe(us(1_000), 7_000_000);
+        // the nominal length of a tick is used
+        assert_eq!(clock.estimate(us(3_000)), Some(9_000_000));
+        assert_eq!(clock.drift(), None);
+
+        let ticks = |ticks| TargetTimestamp {
+            ticks,
+            nanos_per_tick: None,
+            bits: 64,
+        };
+        clock.observe(ticks(10), 7_000_000);
+        assert_eq!(clock.estimate(ticks(10)), None);
+        clock.observe(ticks(20), 8_000_000);
+        assert_eq!(clock.estimate(ticks(30)), Some(9_000_000));
+    }
+
+    #[test]
+    fn burst() {
+        let mut clock = ClockCorrelation::new();
+        for ticks in 0..10 {
+            clock.observe(us(ticks * 1_000), 1_000_000_000);
+        }
+        assert_eq!(clock.drift(), None);
+        assert_eq!(clock.estimate(us(4_500)), Some(1_000_000_000));
+        assert_eq!(clock.estimate(us(5_500)), Some(1_001_000_000));
+    }
+
+    #[test]
+    fn restart() {
+        let mut clock = ClockCorrelation::new();
+        clock.observe(us(1_000_000), 1_000_000_000);
+        clock.observe(us(2_000_000), 2_000_000_000);
+        clock.observe(us(0), 10_000_000_000);
+        assert_eq!(clock.estimate(us(1_000_000)), Some(11_000_000_000));
+    }
+
+    #[test]
+    fn extension() {
+        let mut extension = TimestampExtension::default();
+        let mut extend = |ticks| {
+            extension
+                .extend(TargetTimestamp {
+                    ticks,
+                    nanos_per_tick: Some(1_000),
+                    bits: 32,
+                })
+                .ticks
+        };
+        assert_eq!(extend(0xFFFF_FF00), 0xFFFF_FF00);
+        assert_eq!(extend(0x10), 0x1_0000_0010);
+        assert_eq!(extend(0xFFFF_0000), 0x1_FFFF_0000);
+        assert_eq!(extend(0x20), 0x2_0000_0020);
+        // the target restarted
+        assert_eq!(extend(0x10), 0x10);
+    }
+}
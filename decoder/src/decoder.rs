@@ -3,7 +3,7 @@ use std::{
     ops::Range,
 };
 
-use crate::{Arg, DecodeError, FormatSliceElement, Table};
+use crate::{Arg, DecodeError, Encoding, FormatSliceElement, Table};
 use byteorder::{ReadBytesExt, LE};
 use defmt_parser::{get_max_bitfield_range, Fragment, Parameter, Type};
 
@@ -17,6 +17,24 @@ impl<'t, 'b> Decoder<'t, 'b> {
         Self { table, bytes }
     }
 
+    /// Reads an unsigned integer which is `size` bytes wide on the target.
+    pub fn read_uint(&mut self, size: usize) -> Result<u128, DecodeError> {
+        if size > 1 && self.table.encoding == Encoding::Varint {
+            return read_leb128(&mut self.bytes, size);
+        }
+        Ok(self.bytes.read_uint128::<LE>(size)?)
+    }
+
+    /// Reads a signed integer which is `size` bytes wide on the target.
+    fn read_int(&mut self, size: usize) -> Result<i128, DecodeError> {
+        if size > 1 && self.table.encoding == Encoding::Varint {
+            // zigzag encoding
+            let n = read_leb128(&mut self.bytes, size)?;
+            return Ok((n >> 1) as i128 ^ -((n & 1) as i128));
+        }
+        Ok(self.bytes.read_int128::<LE>(size)?)
+    }
+
     /// Sort and deduplicate `params` so that they can be interpreted correctly during decoding
     fn prepare_params(&self, params: &mut Vec<Parameter>) {
         // deduplicate bitfields by merging them by index
@@ -29,7 +47,7 @@ impl<'t, 'b> Decoder<'t, 'b> {
 
     /// Gets a format string from `bytes` and `table`
     fn get_format(&mut self) -> Result<&'t str, DecodeError> {
-        let index = self.bytes.read_u16::<LE>()? as usize;
+        let index = self.read_uint(2)? as usize;
         let format = self
             .table
             .get_without_level(index)
@@ -44,23 +62,21 @@ impl<'t, 'b> Decoder<'t, 'b> {
         // required: "A|B({:?})" where "{:?}" -> "C|D"
         let num_variants = format.chars().filter(|c| *c == '|').count();
 
-        let discriminant: usize = if u8::try_from(num_variants).is_ok() {
-            self.bytes.read_u8()?.into()
+        let size = if u8::try_from(num_variants).is_ok() {
+            1
         } else if u16::try_from(num_variants).is_ok() {
-            self.bytes.read_u16::<LE>()?.into()
+            2
         } else if u32::try_from(num_variants).is_ok() {
-            self.bytes
-                .read_u32::<LE>()?
-                .try_into()
-                .map_err(|_| DecodeError::Malformed)?
+            4
         } else if u64::try_from(num_variants).is_ok() {
-            self.bytes
-                .read_u64::<LE>()?
-                .try_into()
-                .map_err(|_| DecodeError::Malformed)?
+            8
         } else {
             return Err(DecodeError::Malformed);
         };
+        let discriminant: usize = self
+            .read_uint(size)?
+            .try_into()
+            .map_err(|_| DecodeError::Malformed)?;
 
         format
             .split('|')
@@ -106,17 +122,17 @@ impl<'t, 'b> Decoder<'t, 'b> {
         for param in &params {
             match &param.ty {
                 Type::I8 => args.push(Arg::Ixx(self.bytes.read_i8()? as i128)),
-                Type::I16 => args.push(Arg::Ixx(self.bytes.read_i16::<LE>()? as i128)),
-                Type::I32 => args.push(Arg::Ixx(self.bytes.read_i32::<LE>()? as i128)),
-                Type::I64 => args.push(Arg::Ixx(self.bytes.read_i64::<LE>()? as i128)),
-                Type::I128 => args.push(Arg::Ixx(self.bytes.read_i128::<LE>()?)),
-                Type::Isize => args.push(Arg::Ixx(self.bytes.read_i32::<LE>()? as i128)),
+                Type::I16 => args.push(Arg::Ixx(self.read_int(2)?)),
+                Type::I32 => args.push(Arg::Ixx(self.read_int(4)?)),
+                Type::I64 => args.push(Arg::Ixx(self.read_int(8)?)),
+                Type::I128 => args.push(Arg::Ixx(self.read_int(16)?)),
+                Type::Isize => args.push(Arg::Ixx(self.read_int(4)?)),
                 Type::U8 => args.push(Arg::Uxx(self.bytes.read_u8()? as u128)),
-                Type::U16 => args.push(Arg::Uxx(self.bytes.read_u16::<LE>()? as u128)),
-                Type::U32 => args.push(Arg::Uxx(self.bytes.read_u32::<LE>()? as u128)),
-                Type::U64 => args.push(Arg::Uxx(self.bytes.read_u64::<LE>()? as u128)),
-                Type::U128 => args.push(Arg::Uxx(self.bytes.read_u128::<LE>()?)),
-                Type::Usize => args.push(Arg::Uxx(self.bytes.read_u32::<LE>()? as u128)),
+                Type::U16 => args.push(Arg::Uxx(self.read_uint(2)?)),
+                Type::U32 => args.push(Arg::Uxx(self.read_uint(4)?)),
+                Type::U64 => args.push(Arg::Uxx(self.read_uint(8)?)),
+                Type::U128 => args.push(Arg::Uxx(self.read_uint(16)?)),
+                Type::Usize => args.push(Arg::Uxx(self.read_uint(4)?)),
                 Type::F32 => args.push(Arg::F32(f32::from_bits(self.bytes.read_u32::<LE>()?))),
                 Type::F64 => args.push(Arg::F64(f64::from_bits(self.bytes.read_u64::<LE>()?))),
                 Type::Bool => args.push(Arg::Bool(match self.bytes.read_u8()? {
@@ -125,7 +141,7 @@ impl<'t, 'b> Decoder<'t, 'b> {
                     _ => return Err(DecodeError::Malformed),
                 })),
                 Type::FormatSlice => {
-                    let num_elements = self.bytes.read_u32::<LE>()? as usize;
+                    let num_elements = self.read_uint(4)? as usize;
                     let elements = self.decode_format_slice(num_elements)?;
                     args.push(Arg::FormatSlice { elements });
                 }
@@ -154,11 +170,11 @@ impl<'t, 'b> Decoder<'t, 'b> {
                     let size_after_truncation = highest_byte - lowest_byte + 1; // in octets
 
                     let mut data = match size_after_truncation {
-                        1 => self.bytes.read_u8()? as u128,
-                        2 => self.bytes.read_u16::<LE>()? as u128,
-                        3..=4 => self.bytes.read_u32::<LE>()? as u128,
-                        5..=8 => self.bytes.read_u64::<LE>()? as u128,
-                        9..=16 => self.bytes.read_u128::<LE>()?,
+                        1 => self.read_uint(1)?,
+                        2 => self.read_uint(2)?,
+                        3..=4 => self.read_uint(4)?,
+                        5..=8 => self.read_uint(8)?,
+                        9..=16 => self.read_uint(16)?,
                         _ => unreachable!(),
                     };
 
@@ -167,7 +183,7 @@ impl<'t, 'b> Decoder<'t, 'b> {
                     args.push(Arg::Uxx(data));
                 }
                 Type::Str => {
-                    let str_len = self.bytes.read_u32::<LE>()? as usize;
+                    let str_len = self.read_uint(4)? as usize;
                     let mut arg_str_bytes = vec![];
 
                     // note: went for the suboptimal but simple solution; optimize if necessary
@@ -182,7 +198,7 @@ impl<'t, 'b> Decoder<'t, 'b> {
                     args.push(Arg::Str(arg_str));
                 }
                 Type::IStr => {
-                    let str_index = self.bytes.read_u16::<LE>()? as usize;
+                    let str_index = self.read_uint(2)? as usize;
 
                     let string = self
                         .table
@@ -193,7 +209,7 @@ impl<'t, 'b> Decoder<'t, 'b> {
                 }
                 Type::U8Slice => {
                     // only supports byte slices
-                    let num_elements = self.bytes.read_u32::<LE>()? as usize;
+                    let num_elements = self.read_uint(4)? as usize;
                     let mut arg_slice = vec![];
 
                     // note: went for the suboptimal but simple solution; optimize if necessary
@@ -236,7 +252,7 @@ impl<'t, 'b> Decoder<'t, 'b> {
                 Type::FormatSequence => {
                     let mut seq_args = Vec::new();
                     loop {
-                        let index = self.bytes.read_u16::<LE>()? as usize;
+                        let index = self.read_uint(2)? as usize;
                         if index == 0 {
                             break;
                         }
@@ -261,6 +277,22 @@ impl<'t, 'b> Decoder<'t, 'b> {
     }
 }
 
+/// Reads a LEB128 varint holding an integer which is `size` bytes wide on the target.
+fn read_leb128(bytes: &mut &[u8], size: usize) -> Result<u128, DecodeError> {
+    let mut n = 0;
+    for i in 0..(size * 8).div_ceil(7) {
+        let byte = bytes.read_u8()?;
+        n |= u128::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            if size < 16 && n >> (size * 8) != 0 {
+                return Err(DecodeError::Malformed);
+            }
+            return Ok(n);
+        }
+    }
+    Err(DecodeError::Malformed)
+}
+
 /// Note that this will not change the Bitfield params in place, i.e. if `params` was sorted before
 /// a call to this function, it won't be afterwards.
 fn merge_bitfields(params: &mut Vec<Parameter>) {
@@ -16,13 +16,18 @@ use std::{
 use anyhow::{anyhow, bail, ensure};
 use object::{Object, ObjectSection, ObjectSymbol};
 
-use crate::{BitflagsKey, StringEntry, Table, TableEntry, Tag, DEFMT_VERSIONS};
+use crate::{
+    warn_ambiguous_enums, BitflagsKey, EnumKey, StringEntry, Table, TableEntry, Tag, DEFMT_VERSIONS,
+};
 
 pub fn parse_impl(elf: &[u8], check_version: bool) -> Result<Option<Table>, anyhow::Error> {
     let elf = object::File::parse(elf)?;
     // first pass to extract the `_defmt_version`
     let mut version = None;
     let mut encoding = None;
+    let mut sequence_numbers = false;
+    let mut frame_crc = false;
+    let mut fingerprint = None;
 
     // Note that we check for a quoted and unquoted version symbol, since LLD has a bug that
     // makes it keep the quotes from the linker script.
@@ -75,6 +80,23 @@ pub fn parse_impl(elf: &[u8], check_version: bool) -> Result<Option<Table>, anyh
             }
             encoding = Some(new_encoding);
         }
+
+        if name == "_defmt_sequence_numbers_" {
+            sequence_numbers = true;
+        }
+
+        if name == "_defmt_frame_crc_" {
+            frame_crc = true;
+        }
+
+        if let Some(new_fingerprint) = name.strip_prefix("_defmt_fingerprint_ = ") {
+            if fingerprint.is_some() {
+                bail!("multiple defmt fingerprints found (`defmt::fingerprint!` must only be used once)");
+            }
+            let new_fingerprint = u64::from_str_radix(new_fingerprint, 16)
+                .map_err(|_| anyhow!("malformed defmt fingerprint `{}`", new_fingerprint))?;
+            fingerprint = Some(new_fingerprint);
+        }
     }
 
     // NOTE: We need to make sure to return `Ok(None)`, not `Err`, when defmt is not in use.
@@ -107,6 +129,7 @@ pub fn parse_impl(elf: &[u8], check_version: bool) -> Result<Option<Table>, anyh
     // second pass to demangle symbols
     let mut map = BTreeMap::new();
     let mut bitflags_map = HashMap::new();
+    let mut enum_values = vec![];
     let mut timestamp = None;
     for entry in elf.symbols() {
         let Ok(name) = entry.name() else {
@@ -187,6 +210,45 @@ pub fn parse_impl(elf: &[u8], check_version: bool) -> Result<Option<Table>, anyh
                         value,
                     ));
                 }
+                symbol::SymbolTag::EnumValue => {
+                    // Enum values always occupy 128 bits / 16 bytes.
+                    const ENUM_VALUE_SIZE: u64 = 16;
+
+                    if entry.size() != ENUM_VALUE_SIZE {
+                        bail!("enum value does not occupy 16 bytes (symbol `{}`)", name);
+                    }
+
+                    let defmt_data = defmt_section.data()?;
+                    let addr = entry.address() as usize;
+                    let value = match defmt_data.get(addr..addr + 16) {
+                        Some(bytes) => i128::from_le_bytes(bytes.try_into().unwrap()),
+                        None => bail!(
+                            "enum value at {:#x} outside of defmt section",
+                            entry.address()
+                        ),
+                    };
+                    log::debug!("enum value `{}` has value {}", sym.data(), value);
+
+                    let segments = sym.data().split("::").collect::<Vec<_>>();
+                    let (enum_name, variant_idx, variant_name) = match &*segments {
+                        [enum_name, variant_idx, variant_name] => {
+                            (*enum_name, variant_idx.parse::<u128>()?, *variant_name)
+                        }
+                        _ => bail!("malformed enum value string '{}'", sym.data()),
+                    };
+
+                    enum_values.push((
+                        (
+                            enum_name.to_string(),
+                            sym.package().to_string(),
+                            sym.disambiguator().to_string(),
+                            sym.crate_name().map(str::to_string),
+                            variant_idx,
+                        ),
+                        variant_name.to_string(),
+                        value,
+                    ));
+                }
                 symbol::SymbolTag::Defmt(tag) => {
                     map.insert(
                         entry.address() as usize,
@@ -217,11 +279,29 @@ pub fn parse_impl(elf: &[u8], check_version: bool) -> Result<Option<Table>, anyh
         })
         .collect();
 
+    // Like the bitflags values, put the enum values back in definition order
+    enum_values.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+    let mut enums = BTreeMap::<_, Vec<_>>::new();
+    for ((ident, package, disambig, crate_name, _), variant_name, value) in enum_values {
+        let key = EnumKey {
+            ident,
+            package,
+            disambig,
+            crate_name,
+        };
+        enums.entry(key).or_default().push((variant_name, value));
+    }
+    warn_ambiguous_enums(&enums);
+
     Ok(Some(Table {
         entries: map,
         timestamp,
         bitflags,
+        enums,
         encoding,
+        sequence_numbers,
+        frame_crc,
+        fingerprint,
     }))
 }
 
@@ -32,6 +32,9 @@ pub(super) enum SymbolTag {
     /// `defmt_*` tag that we can interpret.
     Defmt(Tag),
 
+    /// `defmt_enum_value`, a `static` holding the discriminant of a variant of a fieldless enum.
+    EnumValue,
+
     /// Non-`defmt_*` tag for custom tooling.
     Custom(()),
 }
@@ -50,6 +53,7 @@ impl Symbol {
             "defmt_write" => SymbolTag::Defmt(Tag::Write),
             "defmt_timestamp" => SymbolTag::Defmt(Tag::Timestamp),
             "defmt_bitflags_value" => SymbolTag::Defmt(Tag::BitflagsValue),
+            "defmt_enum_value" => SymbolTag::EnumValue,
             "defmt_str" => SymbolTag::Defmt(Tag::Str),
             "defmt_println" => SymbolTag::Defmt(Tag::Println),
             "defmt_trace" => SymbolTag::Defmt(Tag::Trace),
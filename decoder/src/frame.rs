@@ -1,12 +1,18 @@
 use std::{
+    collections::{BTreeMap, HashMap},
     convert::TryFrom,
     fmt::{self, Write as _},
-    mem,
+    mem, slice,
 };
 
-use crate::{Arg, BitflagsKey, Table};
+use crate::{
+    Arg, BitflagsKey, Encoding, Table, TargetTimestamp, DROPPED_FRAMES_INDEX, FINGERPRINT_INDEX,
+};
 use colored::Colorize;
-use defmt_parser::{DisplayHint, Fragment, Level, ParserMode, TimePrecision, Type};
+use defmt_parser::{
+    Alignment, DisplayHint, FormatSpec, Fragment, Level, ParserMode, TimePrecision, Type,
+    FIELD_SEPARATOR,
+};
 use time::{macros::format_description, OffsetDateTime};
 
 /// Used to convert a `i128` value into right target type in hex
@@ -38,37 +44,118 @@ impl std::fmt::UpperHex for I128Hex {
     }
 }
 
+/// Used to print an integer divided by a power of two or ten, exactly
+struct FixedPoint {
+    negative: bool,
+    magnitude: u128,
+    /// Whether the integer is divided by `2^digits`, or else by `10^digits`
+    binary: bool,
+    digits: u8,
+}
+
+impl FixedPoint {
+    fn new(x: i128, hint: &DisplayHint) -> Option<Self> {
+        let mut this = Self::new_unsigned(x.unsigned_abs(), hint)?;
+        this.negative = x < 0;
+        Some(this)
+    }
+
+    fn new_unsigned(x: u128, hint: &DisplayHint) -> Option<Self> {
+        let (binary, digits) = match hint {
+            DisplayHint::QFormat(fraction_bits) => (true, *fraction_bits),
+            DisplayHint::Fixed(decimals) => (false, *decimals),
+            _ => return None,
+        };
+        Some(Self {
+            negative: false,
+            magnitude: x,
+            binary,
+            digits,
+        })
+    }
+}
+
+impl fmt::Display for FixedPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negative {
+            f.write_str("-")?;
+        }
+        if self.binary {
+            // every fractional bit adds one decimal digit, so the loop ends after `digits`
+            // iterations at the latest
+            let mask = (1 << self.digits) - 1;
+            let mut fraction = self.magnitude & mask;
+            write!(f, "{}.", self.magnitude >> self.digits)?;
+            loop {
+                fraction *= 10;
+                write!(f, "{}", fraction >> self.digits)?;
+                fraction &= mask;
+                if fraction == 0 {
+                    return Ok(());
+                }
+            }
+        }
+        let scale = 10u128.pow(self.digits.into());
+        write!(f, "{}", self.magnitude / scale)?;
+        if self.digits > 0 {
+            let width = self.digits.into();
+            write!(f, ".{:0width$}", self.magnitude % scale)?;
+        }
+        Ok(())
+    }
+}
+
 /// A log frame
 #[derive(Debug, PartialEq)]
 pub struct Frame<'t> {
     table: &'t Table,
     level: Option<Level>,
     index: u64,
+    sequence_number: Option<u8>,
+    frames_lost: usize,
+    frames_corrupted: usize,
     timestamp_format: Option<&'t str>,
     timestamp_args: Vec<Arg<'t>>,
     // Format string
     format: &'t str,
     args: Vec<Arg<'t>>,
+    fields: Vec<(&'t str, Arg<'t>)>,
 }
 
 impl<'t> Frame<'t> {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         table: &'t Table,
         level: Option<Level>,
         index: u64,
+        sequence_number: Option<u8>,
         timestamp_format: Option<&'t str>,
         timestamp_args: Vec<Arg<'t>>,
         format: &'t str,
-        args: Vec<Arg<'t>>,
+        mut args: Vec<Arg<'t>>,
     ) -> Self {
+        // the structured fields follow the message, each in the form `<name>={<index>=?}`; their
+        // arguments come after the ones of the message
+        let mut parts = format.split(FIELD_SEPARATOR);
+        let format = parts.next().unwrap_or_default();
+        let names = parts
+            .map(|field| field.split_once('=').map_or(field, |(name, _)| name))
+            .collect::<Vec<_>>();
+        let values = args.split_off(args.len().saturating_sub(names.len()));
+        let fields = names.into_iter().zip(values).collect();
+
         Self {
             table,
             level,
             index,
+            sequence_number,
+            frames_lost: 0,
+            frames_corrupted: 0,
             timestamp_format,
             timestamp_args,
             format,
             args,
+            fields,
         }
     }
 
@@ -99,6 +186,62 @@ impl<'t> Frame<'t> {
         self.index
     }
 
+    /// Returns the rolling sequence number of this frame, if the firmware enabled the
+    /// `sequence-numbers` feature of `defmt`.
+    pub fn sequence_number(&self) -> Option<u8> {
+        self.sequence_number
+    }
+
+    /// Returns how many frames were lost right before this one.
+    ///
+    /// This is detected from gaps in the sequence numbers by the [`StreamDecoder`], so it is
+    /// always `0` if the firmware doesn't send sequence numbers. Since the sequence number is only
+    /// 8 bits wide, losses of more than 255 consecutive frames are under-reported.
+    ///
+    /// [`StreamDecoder`]: crate::StreamDecoder
+    pub fn frames_lost(&self) -> usize {
+        self.frames_lost
+    }
+
+    /// Returns the number of frames the logger dropped, if this frame reports them.
+    ///
+    /// Loggers such as `defmt-rtt` send these frames when their buffer overflowed, once there is
+    /// room again. They don't have a location, a timestamp or a sequence number.
+    pub fn frames_dropped(&self) -> Option<u32> {
+        match (self.index, self.args.as_slice()) {
+            (DROPPED_FRAMES_INDEX, [Arg::Uxx(n)]) => Some(*n as u32),
+            _ => None,
+        }
+    }
+
+    /// Returns the fingerprint of the firmware, if this is a handshake frame sending it.
+    ///
+    /// Loggers such as `defmt-rtt` send these frames if the firmware has a fingerprint, see
+    /// [`Table::fingerprint`]. A handshake frame whose fingerprint doesn't match the table has the
+    /// `ERROR` level. Like the frames reporting dropped frames, they don't have a location, a
+    /// timestamp or a sequence number.
+    pub fn fingerprint(&self) -> Option<u64> {
+        match (self.index, self.args.as_slice()) {
+            (FINGERPRINT_INDEX, [Arg::Uxx(n)]) => Some(*n as u64),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn set_frames_lost(&mut self, frames_lost: usize) {
+        self.frames_lost = frames_lost;
+    }
+
+    /// Returns how many frames were dropped right before this one because their CRC didn't match.
+    ///
+    /// This is always `0` if the firmware doesn't enable the `frame-crc` feature of `defmt`.
+    pub fn frames_corrupted(&self) -> usize {
+        self.frames_corrupted
+    }
+
+    pub(crate) fn set_frames_corrupted(&mut self, frames_corrupted: usize) {
+        self.frames_corrupted = frames_corrupted;
+    }
+
     pub fn timestamp_format(&self) -> Option<&'t str> {
         self.timestamp_format
     }
@@ -107,6 +250,44 @@ impl<'t> Frame<'t> {
         &self.timestamp_args
     }
 
+    /// Returns the numeric value of the timestamp of this frame.
+    ///
+    /// Returns `None` unless the timestamp format has a single unsigned integer parameter, as in
+    /// `defmt::timestamp!("{=u64:us}", ..)`.
+    pub fn target_timestamp(&self) -> Option<TargetTimestamp> {
+        let fragments =
+            defmt_parser::parse(self.timestamp_format?, ParserMode::ForwardsCompatible).ok()?;
+        let mut params = fragments.iter().filter_map(|fragment| match fragment {
+            Fragment::Parameter(param) => Some(param),
+            Fragment::Literal(_) => None,
+        });
+        let (Some(param), None) = (params.next(), params.next()) else {
+            return None;
+        };
+        let Some(Arg::Uxx(ticks)) = self.timestamp_args.get(param.index) else {
+            return None;
+        };
+        let bits = match param.ty {
+            Type::U8 => 8,
+            Type::U16 => 16,
+            Type::U32 | Type::Usize => 32,
+            _ => u64::BITS,
+        };
+        let nanos_per_tick = match param.hint.as_ref().map(without_spec) {
+            Some(
+                DisplayHint::Seconds(precision)
+                | DisplayHint::Time(precision)
+                | DisplayHint::ISO8601(precision),
+            ) => Some(TargetTimestamp::nanos_per_tick(precision)),
+            _ => None,
+        };
+        Some(TargetTimestamp {
+            ticks: u64::try_from(*ticks).ok()?,
+            nanos_per_tick,
+            bits,
+        })
+    }
+
     pub fn format(&self) -> &'t str {
         self.format
     }
@@ -115,6 +296,28 @@ impl<'t> Frame<'t> {
         &self.args
     }
 
+    /// Returns the structured fields of this frame, as in `info!("msg", name = value)`.
+    ///
+    /// Their names and values are not part of [`Frame::format`] and [`Frame::args`].
+    pub fn fields(&self) -> &[(&'t str, Arg<'t>)] {
+        &self.fields
+    }
+
+    /// Returns the names of the structured fields along with their formatted values.
+    pub fn display_fields(&self) -> impl Iterator<Item = (&'t str, String)> + '_ {
+        self.fields
+            .iter()
+            .map(|(name, arg)| (*name, self.format_args("{=?}", slice::from_ref(arg), None)))
+    }
+
+    fn format_message(&self) -> String {
+        let mut message = self.format_args(self.format, &self.args, None);
+        for (name, value) in self.display_fields() {
+            message.push_str(&format!(" {name}={value}"));
+        }
+        message
+    }
+
     fn format_args(&self, format: &str, args: &[Arg], parent_hint: Option<&DisplayHint>) -> String {
         self.format_args_real(format, args, parent_hint).unwrap() // cannot fail, we only write to a `String`
     }
@@ -133,12 +336,31 @@ impl<'t> Frame<'t> {
                     buf.push_str(&lit);
                 }
                 Fragment::Parameter(param) => {
-                    let hint = param.hint.as_ref().or(parent_hint);
+                    // The fill, alignment and width apply to the whole parameter, the precision
+                    // and the hint they come with also to the parameters of nested format strings.
+                    let (spec, hint, precision_hint) = match param.hint.as_ref().or(parent_hint) {
+                        Some(DisplayHint::Formatted { spec, hint }) => {
+                            let precision_hint =
+                                spec.precision.map(|precision| DisplayHint::Formatted {
+                                    spec: FormatSpec {
+                                        precision: Some(precision),
+                                        ..FormatSpec::default()
+                                    },
+                                    hint: hint.clone(),
+                                });
+                            (Some(spec), Some(&**hint), precision_hint)
+                        }
+                        hint => (None, hint, None),
+                    };
+                    let nested_hint = precision_hint.as_ref().or(hint);
+                    let precision = spec.and_then(|spec| spec.precision);
+                    let start = buf.len();
 
-                    match &args[param.index] {
+                    let arg = &args[param.index];
+                    match arg {
                         Arg::Bool(x) => write!(buf, "{x}")?,
-                        Arg::F32(x) => write!(buf, "{}", ryu::Buffer::new().format(*x))?,
-                        Arg::F64(x) => write!(buf, "{}", ryu::Buffer::new().format(*x))?,
+                        Arg::F32(x) => self.format_float(*x, hint, precision, &mut buf)?,
+                        Arg::F64(x) => self.format_float(*x, hint, precision, &mut buf)?,
                         Arg::Uxx(x) => {
                             match param.ty {
                                 Type::BitField(range) => {
@@ -164,31 +386,35 @@ impl<'t> Frame<'t> {
                                     Some(DisplayHint::ISO8601(precision)) => {
                                         self.format_iso8601(*x as u64, precision, &mut buf)?
                                     }
-                                    Some(DisplayHint::Debug) => {
-                                        self.format_u128(*x, parent_hint, &mut buf)?
-                                    }
+                                    Some(DisplayHint::Debug) => self.format_u128(
+                                        *x,
+                                        parent_hint.map(without_spec),
+                                        &mut buf,
+                                    )?,
                                     _ => self.format_u128(*x, hint, &mut buf)?,
                                 },
                             }
                         }
                         Arg::Ixx(x) => self.format_i128(*x, param.ty, hint, &mut buf)?,
-                        Arg::Str(x) | Arg::Preformatted(x) => self.format_str(x, hint, &mut buf)?,
-                        Arg::IStr(x) => self.format_str(x, hint, &mut buf)?,
-                        Arg::Format { format, args } => match parent_hint {
+                        Arg::Str(x) | Arg::Preformatted(x) => {
+                            self.format_str(truncate(x, precision), hint, &mut buf)?
+                        }
+                        Arg::IStr(x) => self.format_str(truncate(x, precision), hint, &mut buf)?,
+                        Arg::Format { format, args } => match parent_hint.map(without_spec) {
                             Some(DisplayHint::Ascii) => {
                                 buf.push_str(&self.format_args(format, args, parent_hint));
                             }
-                            _ => buf.push_str(&self.format_args(format, args, hint)),
+                            _ => buf.push_str(&self.format_args(format, args, nested_hint)),
                         },
                         Arg::FormatSequence { args } => {
                             for arg in args {
-                                buf.push_str(&self.format_args("{=?}", &[arg.clone()], hint))
+                                buf.push_str(&self.format_args("{=?}", &[arg.clone()], nested_hint))
                             }
                         }
                         Arg::FormatSlice { elements } => {
                             match hint {
-                                // Filter Ascii Hints, which contains u8 byte slices
-                                Some(DisplayHint::Ascii)
+                                // Filter Ascii and Hexdump Hints, which contains u8 byte slices
+                                Some(DisplayHint::Ascii | DisplayHint::Hexdump)
                                     if elements.iter().filter(|e| e.format == "{=u8}").count()
                                         != 0 =>
                                 {
@@ -215,7 +441,7 @@ impl<'t> Frame<'t> {
                                         buf.write_str(&self.format_args(
                                             element.format,
                                             &element.args,
-                                            hint,
+                                            nested_hint,
                                         ))?;
                                     }
                                     buf.write_str("]")?;
@@ -225,6 +451,10 @@ impl<'t> Frame<'t> {
                         Arg::Slice(x) => self.format_bytes(x, hint, &mut buf)?,
                         Arg::Char(c) => write!(buf, "{c}")?,
                     }
+
+                    if let Some(spec) = spec {
+                        pad(&mut buf, start, spec, is_number(arg));
+                    }
                 }
             }
         }
@@ -321,6 +551,19 @@ impl<'t> Frame<'t> {
                     }
                 }
             }
+            Some(DisplayHint::Enum { name, crate_name }) => {
+                match i128::try_from(x)
+                    .ok()
+                    .and_then(|x| self.enum_variant(name, crate_name.as_deref(), x))
+                {
+                    Some(variant) => write!(buf, "{variant}")?,
+                    None => write!(buf, "{x}")?,
+                }
+            }
+            Some(hint @ (DisplayHint::QFormat(_) | DisplayHint::Fixed(_))) => {
+                write!(buf, "{}", FixedPoint::new_unsigned(x, hint).unwrap())?
+            }
+            Some(DisplayHint::Unit(unit)) => write!(buf, "{x} {unit}")?,
             _ => write!(buf, "{x}")?,
         }
         Ok(())
@@ -362,11 +605,40 @@ impl<'t> Frame<'t> {
                     (true, true) => write!(buf, "{value:#0zero_pad$X}")?,
                 }
             }
+            Some(DisplayHint::Enum { name, crate_name }) => {
+                match self.enum_variant(name, crate_name.as_deref(), x) {
+                    Some(variant) => write!(buf, "{variant}")?,
+                    None => write!(buf, "{x}")?,
+                }
+            }
+            Some(hint @ (DisplayHint::QFormat(_) | DisplayHint::Fixed(_))) => {
+                write!(buf, "{}", FixedPoint::new(x, hint).unwrap())?
+            }
+            Some(DisplayHint::Unit(unit)) => write!(buf, "{x} {unit}")?,
             _ => write!(buf, "{x}")?,
         }
         Ok(())
     }
 
+    /// Returns the name of the variant of the enum `name` with the discriminant `value`.
+    ///
+    /// If `crate_name` is set, only the enums of that crate are considered. Returns `None` if the
+    /// enum or the variant is unknown, or if several of the enums considered are named `name`.
+    fn enum_variant(&self, name: &str, crate_name: Option<&str>, value: i128) -> Option<&str> {
+        let mut enums = self.table.enums.iter().filter(|(key, _)| match crate_name {
+            Some(crate_name) => key.ident == name && key.is_in_crate(crate_name),
+            None => key.ident == name,
+        });
+        let (_, variants) = enums.next()?;
+        if enums.next().is_some() {
+            return None;
+        }
+        variants
+            .iter()
+            .find(|(_, discriminant)| *discriminant == value)
+            .map(|(variant, _)| variant.as_str())
+    }
+
     fn format_bytes(
         &self,
         bytes: &[u8],
@@ -415,6 +687,29 @@ impl<'t> Frame<'t> {
                 }
                 buf.push(']');
             }
+            Some(DisplayHint::Hexdump) => {
+                // like `xxd`, on lines of their own:
+                // 00000000: 4865 6c6c 6f2c 2077 6f72 6c64 2100 0102  Hello, world!...
+                for (line, chunk) in bytes.chunks(16).enumerate() {
+                    write!(buf, "\n{:08x}:", line * 16)?;
+                    for (i, byte) in chunk.iter().enumerate() {
+                        if i % 2 == 0 {
+                            buf.push(' ');
+                        }
+                        write!(buf, "{byte:02x}")?;
+                    }
+                    // line up the ASCII column of the last line
+                    let missing = 16 - chunk.len();
+                    buf.push_str(&" ".repeat(missing * 2 + missing / 2));
+                    buf.push_str("  ");
+                    for byte in chunk {
+                        match byte.is_ascii_graphic() || *byte == b' ' {
+                            true => buf.push(*byte as char),
+                            false => buf.push('.'),
+                        }
+                    }
+                }
+            }
             Some(DisplayHint::Cbor) => {
                 use core::fmt::Write;
                 let parsed = cbor_edn::Sequence::from_cbor(bytes);
@@ -430,6 +725,62 @@ impl<'t> Frame<'t> {
         Ok(())
     }
 
+    fn format_float<F>(
+        &self,
+        x: F,
+        hint: Option<&DisplayHint>,
+        precision: Option<usize>,
+        buf: &mut String,
+    ) -> Result<(), fmt::Error>
+    where
+        F: defmt_json_schema::Float + ryu::Float + fmt::Display + fmt::LowerExp + fmt::UpperExp,
+    {
+        // the hints for integers print the raw bits, in hex and binary with all leading zeros
+        let width = |digits: u32, alternate: bool| digits as usize + 2 * alternate as usize;
+        let bits_hint = match hint {
+            Some(DisplayHint::Hexadecimal {
+                alternate,
+                uppercase,
+                zero_pad,
+            }) => Some(DisplayHint::Hexadecimal {
+                alternate: *alternate,
+                uppercase: *uppercase,
+                zero_pad: (*zero_pad).max(width(F::BITS / 4, *alternate)),
+            }),
+            Some(DisplayHint::Binary {
+                alternate,
+                zero_pad,
+            }) => Some(DisplayHint::Binary {
+                alternate: *alternate,
+                zero_pad: (*zero_pad).max(width(F::BITS, *alternate)),
+            }),
+            Some(hint @ DisplayHint::Octal { .. }) => Some(hint.clone()),
+            _ => None,
+        };
+        if let Some(hint) = bits_hint {
+            return self.format_u128(x.bits().into(), Some(&hint), buf);
+        }
+
+        // like in JSON, `NaN` stands for the default quiet NaN, and any other payload is shown
+        if let Some(s) = defmt_json_schema::non_finite_to_string(x) {
+            buf.push_str(&s);
+            return Ok(());
+        }
+
+        match (hint, precision) {
+            (Some(DisplayHint::Scientific { uppercase: false }), Some(precision)) => {
+                write!(buf, "{x:.precision$e}")
+            }
+            (Some(DisplayHint::Scientific { uppercase: false }), None) => write!(buf, "{x:e}"),
+            (Some(DisplayHint::Scientific { uppercase: true }), Some(precision)) => {
+                write!(buf, "{x:.precision$E}")
+            }
+            (Some(DisplayHint::Scientific { uppercase: true }), None) => write!(buf, "{x:E}"),
+            (_, Some(precision)) => write!(buf, "{x:.precision$}"),
+            (_, None) => write!(buf, "{}", ryu::Buffer::new().format(x)),
+        }
+    }
+
     fn format_str(
         &self,
         s: &str,
@@ -519,6 +870,88 @@ impl<'t> Frame<'t> {
     }
 }
 
+/// Returns the display hint that `hint` comes with, without its fill, alignment, width and
+/// precision.
+fn without_spec(hint: &DisplayHint) -> &DisplayHint {
+    match hint {
+        DisplayHint::Formatted { hint, .. } => hint,
+        hint => hint,
+    }
+}
+
+/// Returns the first `precision` characters of `s`, if there is a precision.
+fn truncate(s: &str, precision: Option<usize>) -> &str {
+    match precision.and_then(|precision| s.char_indices().nth(precision)) {
+        Some((end, _)) => &s[..end],
+        None => s,
+    }
+}
+
+/// Pads what was written to `buf` after `start` to the width of `spec`.
+fn pad(buf: &mut String, start: usize, spec: &FormatSpec, number: bool) {
+    let len = buf[start..].chars().count();
+    let padding = match spec.width {
+        Some(width) if width > len => width - len,
+        _ => return,
+    };
+    // like in core::fmt, numbers are aligned to the right by default
+    let default = match number {
+        true => Alignment::Right,
+        false => Alignment::Left,
+    };
+    let (before, after) = match spec.align.unwrap_or(default) {
+        Alignment::Left => (0, padding),
+        Alignment::Center => (padding / 2, padding - padding / 2),
+        Alignment::Right => (padding, 0),
+    };
+    let fill = spec.fill.to_string();
+    buf.insert_str(start, &fill.repeat(before));
+    buf.push_str(&fill.repeat(after));
+}
+
+/// Returns whether `arg` is a number, possibly formatted through `Format`, like `{}` with an
+/// `u8`.
+fn is_number(arg: &Arg) -> bool {
+    match arg {
+        Arg::Uxx(_) | Arg::Ixx(_) | Arg::F32(_) | Arg::F64(_) => true,
+        Arg::Format { format, args } => {
+            matches!(args.as_slice(), [arg] if is_number(arg))
+                && format.starts_with('{')
+                && format.find('}') == Some(format.len() - 1)
+        }
+        _ => false,
+    }
+}
+
+/// Formats `ticks` with the timestamp format `format`, which has a single integer parameter, as
+/// [`Frame::target_timestamp`] requires.
+pub(crate) fn format_timestamp(format: &str, ticks: u64) -> String {
+    // integers don't need anything from the table
+    let table = Table {
+        timestamp: None,
+        entries: BTreeMap::new(),
+        bitflags: HashMap::new(),
+        enums: BTreeMap::new(),
+        encoding: Encoding::Raw,
+        sequence_numbers: false,
+        frame_crc: false,
+        fingerprint: None,
+    };
+    let index = defmt_parser::parse(format, ParserMode::ForwardsCompatible)
+        .ok()
+        .and_then(|fragments| {
+            fragments.into_iter().find_map(|fragment| match fragment {
+                Fragment::Parameter(param) => Some(param.index),
+                Fragment::Literal(_) => None,
+            })
+        })
+        .unwrap_or_default();
+    let mut args = vec![Arg::Uxx(0); index];
+    args.push(Arg::Uxx(ticks.into()));
+    let frame = Frame::new(&table, None, 0, None, Some(format), args, "", vec![]);
+    frame.format_args(format, &frame.timestamp_args, None)
+}
+
 pub struct DisplayTimestamp<'t> {
     frame: &'t Frame<'t>,
 }
@@ -540,10 +973,7 @@ pub struct DisplayMessage<'t> {
 
 impl fmt::Display for DisplayMessage<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let args = self
-            .frame
-            .format_args(self.frame.format, &self.frame.args, None);
-        f.write_str(&args)
+        f.write_str(&self.frame.format_message())
     }
 }
 
@@ -591,9 +1021,7 @@ impl fmt::Display for DisplayFrame<'_> {
             })
             .unwrap_or_default();
 
-        let args = self
-            .frame
-            .format_args(self.frame.format, &self.frame.args, None);
+        let args = self.frame.format_message();
 
         write!(f, "{timestamp}{level}{args}")
     }
@@ -10,11 +10,13 @@ pub const DEFMT_VERSIONS: &[&str] = &["3", "4"];
 #[deprecated = "Please use DEFMT_VERSIONS instead"]
 pub const DEFMT_VERSION: &str = DEFMT_VERSIONS[1];
 
+mod clock;
 mod decoder;
 mod elf2table;
 mod frame;
 pub mod log;
 pub mod stream;
+mod table_file;
 
 use std::{
     collections::{BTreeMap, HashMap},
@@ -29,11 +31,33 @@ pub use defmt_parser::Level;
 use crate::{decoder::Decoder, elf2table::parse_impl};
 
 pub use crate::{
+    clock::{ClockCorrelation, TargetTimestamp},
     elf2table::{Location, Locations},
     frame::Frame,
     stream::StreamDecoder,
 };
 
+/// String index of the frames in which a logger reports how many frames it dropped.
+///
+/// These frames carry the number of dropped frames as a `u32`, and neither a sequence number
+/// nor a timestamp. The linker script keeps the indices of interned strings below this value.
+const DROPPED_FRAMES_INDEX: u64 = 0xFFFF;
+
+/// Format string of the frames reporting dropped frames
+const DROPPED_FRAMES_FORMAT: &str = "{=u32} frames dropped";
+
+/// String index of the handshake frames in which a logger sends the fingerprint of the firmware.
+///
+/// These frames carry the fingerprint as a `u64`, and neither a sequence number nor a timestamp.
+const FINGERPRINT_INDEX: u64 = 0xFFFE;
+
+/// Format string of the handshake frames whose fingerprint matches the table
+const FINGERPRINT_FORMAT: &str = "firmware fingerprint {=u64:#x}";
+
+/// Format string of the handshake frames whose fingerprint doesn't match the table
+const FINGERPRINT_MISMATCH_FORMAT: &str =
+    "firmware fingerprint {=u64:#x} doesn't match the ELF file; logs may be decoded wrongly";
+
 /// Specifies the origin of a format string
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Tag {
@@ -119,6 +143,46 @@ struct BitflagsKey {
     crate_name: Option<String>,
 }
 
+/// Data that uniquely identifies a fieldless enum deriving `Format`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct EnumKey {
+    /// Name of the enum, as used by the `enum(Name)` display hint
+    ident: String,
+    package: String,
+    disambig: String,
+    /// Name of the crate defining the enum, if the symbol has it
+    crate_name: Option<String>,
+}
+
+impl EnumKey {
+    /// Returns whether the enum is defined in the crate `crate_name`, as written in Rust paths.
+    fn is_in_crate(&self, crate_name: &str) -> bool {
+        match &self.crate_name {
+            Some(name) => name == crate_name,
+            // the crate name of a library defaults to its package name, with `-` replaced by `_`
+            None => self.package.replace('-', "_") == crate_name,
+        }
+    }
+}
+
+/// Warns about the enums which share their name with another one.
+///
+/// The `enum(Name)` display hint can't tell them apart, so it prints their values as is, unless it
+/// names the crate of the enum.
+fn warn_ambiguous_enums(enums: &BTreeMap<EnumKey, Vec<(String, i128)>>) {
+    // the keys are sorted by name first
+    let mut previous = None;
+    for key in enums.keys() {
+        if previous == Some(&key.ident) {
+            ::log::warn!(
+                "several enums are named `{0}`; values with the `enum({0})` display hint are printed as numbers, unless the hint names the crate, like `enum(crate_name::{0})`",
+                key.ident
+            );
+        }
+        previous = Some(&key.ident);
+    }
+}
+
 /// How a defmt frame is encoded
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[non_exhaustive]
@@ -127,6 +191,12 @@ pub enum Encoding {
     Raw,
     /// [Reverse Zero-compressing COBS encoding](https://github.com/Dirbaio/rzcobs)
     Rzcobs,
+    /// rzCOBS encoding, with string indices, lengths and integers written as LEB128 varints.
+    Varint,
+    /// [Consistent Overhead Byte Stuffing](https://en.wikipedia.org/wiki/Consistent_Overhead_Byte_Stuffing)
+    Cobs,
+    /// HDLC-like framing with a CRC-16/X-25 frame check sequence
+    HdlcCrc16,
 }
 
 impl FromStr for Encoding {
@@ -136,6 +206,9 @@ impl FromStr for Encoding {
         match s {
             "raw" => Ok(Encoding::Raw),
             "rzcobs" => Ok(Encoding::Rzcobs),
+            "varint" => Ok(Encoding::Varint),
+            "cobs" => Ok(Encoding::Cobs),
+            "hdlc-crc16" => Ok(Encoding::HdlcCrc16),
             _ => anyhow::bail!("Unknown defmt encoding '{}' specified. This is a bug.", s),
         }
     }
@@ -147,6 +220,20 @@ impl Encoding {
         match self {
             Encoding::Raw => false,
             Encoding::Rzcobs => true,
+            Encoding::Varint => true,
+            Encoding::Cobs => true,
+            Encoding::HdlcCrc16 => true,
+        }
+    }
+
+    /// The byte which ends every frame, if this encoding has one.
+    ///
+    /// A stream can be resynchronized to the frame boundaries by skipping past it.
+    pub const fn frame_separator(&self) -> Option<u8> {
+        match self {
+            Encoding::Raw => None,
+            Encoding::Rzcobs | Encoding::Varint | Encoding::Cobs => Some(0x00),
+            Encoding::HdlcCrc16 => Some(0x7e),
         }
     }
 }
@@ -157,7 +244,15 @@ pub struct Table {
     timestamp: Option<TableEntry>,
     entries: BTreeMap<usize, TableEntry>,
     bitflags: HashMap<BitflagsKey, Vec<(String, u128)>>,
+    /// The variants of the fieldless enums deriving `Format` and their discriminants
+    enums: BTreeMap<EnumKey, Vec<(String, i128)>>,
     encoding: Encoding,
+    /// Whether frame headers contain a sequence number (`sequence-numbers` feature of `defmt`)
+    sequence_numbers: bool,
+    /// Whether frames end with a CRC (`frame-crc` feature of `defmt`)
+    frame_crc: bool,
+    /// The fingerprint of the firmware (`defmt::fingerprint!`)
+    fingerprint: Option<u64>,
 }
 
 impl Table {
@@ -228,15 +323,50 @@ impl Table {
     ///
     /// * `bytes`
     ///   * contains the data sent by the device that logs.
-    ///   * contains the [log string index, timestamp, optional fmt string args]
+    ///   * contains the [log string index, optional sequence number, timestamp, optional fmt string args]
     pub fn decode<'t>(
         &'t self,
-        mut bytes: &[u8],
+        bytes: &[u8],
     ) -> Result<(Frame<'t>, /* consumed: */ usize), DecodeError> {
         let len = bytes.len();
-        let index = bytes.read_u16::<LE>()? as u64;
-
         let mut decoder = Decoder::new(self, bytes);
+        let index = decoder.read_uint(2)? as u64;
+        if index == DROPPED_FRAMES_INDEX {
+            let dropped = decoder.bytes.read_u32::<LE>()?;
+            let frame = Frame::new(
+                self,
+                Some(Level::Warn),
+                index,
+                None,
+                None,
+                vec![],
+                DROPPED_FRAMES_FORMAT,
+                vec![Arg::Uxx(dropped.into())],
+            );
+            return Ok((frame, len - decoder.bytes.len()));
+        }
+        if index == FINGERPRINT_INDEX {
+            let fingerprint = decoder.bytes.read_u64::<LE>()?;
+            let (level, format) = match self.fingerprint == Some(fingerprint) {
+                true => (Level::Info, FINGERPRINT_FORMAT),
+                false => (Level::Error, FINGERPRINT_MISMATCH_FORMAT),
+            };
+            let frame = Frame::new(
+                self,
+                Some(level),
+                index,
+                None,
+                None,
+                vec![],
+                format,
+                vec![Arg::Uxx(fingerprint.into())],
+            );
+            return Ok((frame, len - decoder.bytes.len()));
+        }
+        let sequence_number = match self.sequence_numbers {
+            true => Some(decoder.bytes.read_u8()?),
+            false => None,
+        };
 
         let mut timestamp_format = None;
         let mut timestamp_args = Vec::new();
@@ -256,6 +386,7 @@ impl Table {
             self,
             level,
             index,
+            sequence_number,
             timestamp_format,
             timestamp_args,
             format,
@@ -269,7 +400,9 @@ impl Table {
     pub fn new_stream_decoder(&self) -> Box<dyn StreamDecoder + '_> {
         match self.encoding {
             Encoding::Raw => Box::new(stream::Raw::new(self)),
-            Encoding::Rzcobs => Box::new(stream::Rzcobs::new(self)),
+            Encoding::Rzcobs | Encoding::Varint => Box::new(stream::Rzcobs::new(self)),
+            Encoding::Cobs => Box::new(stream::Cobs::new(self)),
+            Encoding::HdlcCrc16 => Box::new(stream::Hdlc::new(self)),
         }
     }
 
@@ -280,6 +413,23 @@ impl Table {
     pub fn has_timestamp(&self) -> bool {
         self.timestamp.is_some()
     }
+
+    /// Returns the fingerprint of the firmware, if it has one.
+    ///
+    /// Firmware with a fingerprint sends it in handshake frames, see [`Frame::fingerprint`].
+    pub fn fingerprint(&self) -> Option<u64> {
+        self.fingerprint
+    }
+
+    /// Whether frames carry a sequence number, see [`Frame::sequence_number`].
+    pub fn has_sequence_numbers(&self) -> bool {
+        self.sequence_numbers
+    }
+
+    /// Whether frames end with a CRC, see [`Frame::frames_corrupted`].
+    pub fn has_frame_crc(&self) -> bool {
+        self.frame_crc
+    }
 }
 
 // NOTE follows `parser::Type`
@@ -364,7 +514,11 @@ mod tests {
             timestamp: None,
             entries: entries.into_iter().enumerate().collect(),
             bitflags: Default::default(),
+            enums: Default::default(),
             encoding: Encoding::Raw,
+            sequence_numbers: false,
+            frame_crc: false,
+            fingerprint: None,
         }
     }
 
@@ -379,7 +533,11 @@ mod tests {
             )),
             entries: entries.into_iter().enumerate().collect(),
             bitflags: Default::default(),
+            enums: Default::default(),
             encoding: Encoding::Raw,
+            sequence_numbers: false,
+            frame_crc: false,
+            fingerprint: None,
         }
     }
 
@@ -402,7 +560,11 @@ mod tests {
                 "{=u8:us}".to_owned(),
             )),
             bitflags: Default::default(),
+            enums: Default::default(),
             encoding: Encoding::Raw,
+            sequence_numbers: false,
+            frame_crc: false,
+            fingerprint: None,
         };
 
         let frame = table.decode(bytes).unwrap().0;
@@ -429,6 +591,7 @@ mod tests {
                     Some(Level::Info),
                     0,
                     None,
+                    None,
                     vec![],
                     "Hello, world!",
                     vec![],
@@ -450,6 +613,7 @@ mod tests {
                     Some(Level::Debug),
                     1,
                     None,
+                    None,
                     vec![],
                     "The answer is {=u8}!",
                     vec![Arg::Uxx(42)],
@@ -494,6 +658,7 @@ mod tests {
                     Some(Level::Info),
                     0,
                     None,
+                    None,
                     vec![],
                     FMT,
                     vec![
@@ -538,6 +703,7 @@ mod tests {
                     Some(Level::Info),
                     0,
                     None,
+                    None,
                     vec![],
                     "The answer is {0=u8} {0=u8}!",
                     vec![Arg::Uxx(42)],
@@ -560,6 +726,7 @@ mod tests {
                     Some(Level::Info),
                     1,
                     None,
+                    None,
                     vec![],
                     "The answer is {1=u16} {0=u8} {1=u16}!",
                     vec![Arg::Uxx(42), Arg::Uxx(0xffff)],
@@ -592,6 +759,7 @@ mod tests {
                     Some(Level::Info),
                     0,
                     None,
+                    None,
                     vec![],
                     "x={=?}",
                     vec![Arg::Format {
@@ -633,6 +801,7 @@ mod tests {
                     Some(Level::Info),
                     0,
                     None,
+                    None,
                     vec![],
                     "{=__internal_FormatSequence}",
                     vec![Arg::FormatSequence {
@@ -680,6 +849,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn fields() {
+        // defmt::info!("conn open {=u8}", 3, peer = Foo { x: 42 }, rssi = -40_i8);
+        let entries = vec![
+            TableEntry::new_without_symbol(
+                Tag::Info,
+                "conn open {=u8}\u{1f}peer={1=?}\u{1f}rssi={2=?}".to_owned(),
+            ),
+            TableEntry::new_without_symbol(Tag::Derived, "Foo {{ x: {=u8} }}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Derived, "{=i8}".to_owned()),
+        ];
+
+        let table = test_table_with_timestamp(entries, "{=u8:us}");
+
+        let bytes = [
+            0, 0, // index
+            2, // timestamp
+            3, // positional argument
+            1, 0,  // index of the struct
+            42, // Foo.x
+            2, 0,   // index of the i8
+            216, // rssi
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.format(), "conn open {=u8}");
+        assert_eq!(frame.args(), [Arg::Uxx(3)]);
+        assert_eq!(
+            frame.fields(),
+            [
+                (
+                    "peer",
+                    Arg::Format {
+                        format: "Foo {{ x: {=u8} }}",
+                        args: vec![Arg::Uxx(42)]
+                    }
+                ),
+                (
+                    "rssi",
+                    Arg::Format {
+                        format: "{=i8}",
+                        args: vec![Arg::Ixx(-40)]
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            frame.display(false).to_string(),
+            "0.000002 INFO conn open 3 peer=Foo { x: 42 } rssi=-40"
+        );
+    }
+
     #[test]
     fn display_i16_with_hex_hint() {
         // defmt::info!("x: {=i16:#x},y: {=i16:#x},z: {=i16:#x}", -1_i16, -100_i16, -1000_i16);
@@ -794,6 +1015,17 @@ mod tests {
             frame.display(false).to_string(),
             "0.000002 INFO [Data { name: b\"Hi\" }]",
         );
+        // with a precision, which is passed on to the nested parameters along with the hint
+        let mut table = table;
+        table.entries.insert(
+            3,
+            TableEntry::new_without_symbol(Tag::Info, "{=[?]:.5a}".to_owned()),
+        );
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.display(false).to_string(),
+            "0.000002 INFO [Data { name: b\"Hi\" }]",
+        );
     }
 
     #[test]
@@ -811,6 +1043,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn target_timestamp() {
+        let entries = vec![TableEntry::new_without_symbol(Tag::Info, "x".to_owned())];
+        let bytes = [
+            0, 0, // index
+            2, // timestamp
+        ];
+
+        let table = test_table_with_timestamp(entries.clone(), "{=u8:ms}");
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.target_timestamp(),
+            Some(TargetTimestamp {
+                ticks: 2,
+                nanos_per_tick: Some(1_000_000),
+                bits: 8,
+            })
+        );
+
+        let table = test_table_with_timestamp(entries.clone(), "tick {=u8}");
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.target_timestamp(),
+            Some(TargetTimestamp {
+                ticks: 2,
+                nanos_per_tick: None,
+                bits: 8,
+            })
+        );
+
+        // with a width
+        let table = test_table_with_timestamp(entries.clone(), "{=u8:>8ms}");
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.target_timestamp(),
+            Some(TargetTimestamp {
+                ticks: 2,
+                nanos_per_tick: Some(1_000_000),
+                bits: 8,
+            })
+        );
+
+        let table = test_table_with_timestamp(entries, "{=i8}");
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.target_timestamp(), None);
+    }
+
     #[test]
     fn bools_simple() {
         let bytes = [
@@ -822,6 +1101,187 @@ mod tests {
         decode_and_expect("my bool={=bool}", &bytes, "0.000002 INFO my bool=true");
     }
 
+    #[test]
+    fn enum_variant() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "{=u8:enum(Mode)} {=i8:enum(Mode)} {=u8:enum(Unknown)}".to_owned(),
+        )];
+        let mut table = test_table(entries);
+        let mode = |package: &str| EnumKey {
+            ident: "Mode".into(),
+            package: package.into(),
+            disambig: "1234".into(),
+            crate_name: Some(package.into()),
+        };
+        table.enums.insert(
+            mode("app"),
+            vec![("Off".into(), 0), ("On".into(), 1), ("Fault".into(), -1)],
+        );
+
+        let decode = |table: &Table, bytes: &[u8]| {
+            let frame = table.decode(bytes).unwrap().0;
+            frame.display_message().to_string()
+        };
+        assert_eq!(decode(&table, &[0, 0, 1, 0xff, 1]), "On Fault 1");
+        // unknown values are shown as is
+        assert_eq!(decode(&table, &[0, 0, 2, 3, 1]), "2 3 1");
+        assert_eq!(decode(&table, &[0, 0, 0xff, 1, 1]), "255 On 1");
+
+        // an enum with the same name from another crate makes the name ambiguous, even for the
+        // values which only one of them defines
+        table.enums.insert(mode("driver"), vec![("Auto".into(), 2)]);
+        assert_eq!(decode(&table, &[0, 0, 1, 2, 1]), "1 2 1");
+
+        // unless the display hint names the crate
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "{=u8:enum(app::Mode)} {=u8:enum(driver::Mode)} {=u8:enum(other::Mode)}".to_owned(),
+        )];
+        table.entries = entries.into_iter().enumerate().collect();
+        assert_eq!(decode(&table, &[0, 0, 1, 2, 1]), "On Auto 1");
+    }
+
+    #[test]
+    fn float_hints() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "{=f32:x} {=f32:#x} {=f64:X} {=f32:e} {=f64:.2E} {=f32} {=f32:.1} {=f64}".to_owned(),
+        )];
+        let table = test_table(entries);
+
+        #[rustfmt::skip]
+        let bytes = [
+            0, 0, // index
+            0x00, 0x00, 0x80, 0x3f, // f32 1.0
+            0x01, 0x00, 0x00, 0x00, // f32 1e-45
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, // f64 -2.0
+            0x00, 0x80, 0xbb, 0x44, // f32 1500.0
+            0x46, 0xd2, 0x6e, 0xf4, 0x31, 0x1f, 0x20, 0x3f, // f64 0.000123
+            0x00, 0x00, 0xc0, 0x7f, // f32 NaN
+            0x01, 0x00, 0xc0, 0xff, // f32 -NaN with payload
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0, 0x7f, // f64 signaling NaN
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.display_message().to_string(),
+            "3f800000 0x00000001 C000000000000000 1.5e3 1.23E-4 NaN -NaN(0x400001) NaN(0x1)"
+        );
+    }
+
+    #[test]
+    fn hexdump() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "data: {=[u8]:hexdump}".to_owned(),
+        )];
+        let table = test_table(entries);
+
+        let mut bytes = vec![
+            0, 0, // index
+            20, 0, 0, 0, // length of the slice
+        ];
+        bytes.extend_from_slice(b"Hello, world!\x00\x01\x02\x03\x04\x05\x06");
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.display_message().to_string(),
+            "data: \n\
+             00000000: 4865 6c6c 6f2c 2077 6f72 6c64 2100 0102  Hello, world!...\n\
+             00000010: 0304 0506                                ...."
+        );
+    }
+
+    #[test]
+    fn hexdump_format_slice() {
+        // `{:hexdump}` with a `&[u8]`
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "data: {=?:hexdump}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Prim, "{=[?]}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Prim, "{=u8}".to_owned()),
+        ];
+        let table = test_table(entries);
+
+        let bytes = [
+            0, 0, // index
+            1, 0, // Format index to table entry: `{=[?]}`
+            3, 0, 0, 0, // number of elements in `FormatSlice`
+            2, 0, // Format index to table entry: `{=u8}`
+            b'a', 0xff, b'z',
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.display_message().to_string(),
+            "data: \n\
+             00000000: 61ff 7a                                  a.z"
+        );
+    }
+
+    #[test]
+    fn width_and_precision() {
+        let entries = vec![
+            TableEntry::new_without_symbol(
+                Tag::Info,
+                "[{=f32:.2}] [{=str:>6}] [{=str:.2}] [{=u8:*^7}] [{=i8:<4}] [{:.3}] [{:6}]"
+                    .to_owned(),
+            ),
+            TableEntry::new_without_symbol(Tag::Prim, "{=f32}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Prim, "{=u8}".to_owned()),
+        ];
+        let table = test_table(entries);
+
+        #[rustfmt::skip]
+        let bytes = [
+            0, 0,                   // index
+            0xd0, 0x0f, 0x49, 0x40, // f32 3.14159
+            3, 0, 0, 0,             // length of the string
+            b'a', b'b', b'c',       // string "abc"
+            3, 0, 0, 0,             // length of the string
+            b'a', b'b', b'c',       // string "abc"
+            42,                     // u8
+            0xff,                   // i8
+            1, 0,                   // index of `{=f32}`
+            0x00, 0x00, 0xc0, 0x3f, // f32 1.5
+            2, 0,                   // index of `{=u8}`
+            42,                     // u8
+        ];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.display_message().to_string(),
+            "[3.14] [   abc] [ab] [**42***] [-1  ] [1.500] [    42]"
+        );
+    }
+
+    #[test]
+    fn fixed_point() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "{=i16:q15} {=u16:q15} {=i32:fixed(3)} {=u8:fixed(0)} {=u16:mV}".to_owned(),
+        )];
+        let table = test_table(entries);
+
+        let decode = |bytes: &[u8]| table.decode(bytes).unwrap().0.display_message().to_string();
+        #[rustfmt::skip]
+        let bytes = [
+            0, 0,                   // index
+            0x00, 0x40,             // i16
+            0x01, 0x80,             // u16
+            0x2e, 0xfb, 0xff, 0xff, // i32
+            7,                      // u8
+            0xd2, 0x04,             // u16
+        ];
+        assert_eq!(decode(&bytes), "0.5 1.000030517578125 -1.234 7 1234 mV");
+        #[rustfmt::skip]
+        let bytes = [
+            0, 0,
+            0x00, 0x80,
+            0x00, 0x00,
+            0x05, 0x00, 0x00, 0x00,
+            0,
+            0, 0,
+        ];
+        assert_eq!(decode(&bytes), "-1.0 0.0 0.005 0 0 mV");
+    }
+
     #[test]
     fn bitfields() {
         let bytes = [
@@ -1079,6 +1539,354 @@ mod tests {
         );
     }
 
+    #[test]
+    fn sequence_numbers() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "The answer is {=u8}!".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.sequence_numbers = true;
+
+        let bytes = [
+            0, 0,  // index
+            7,  // sequence number
+            42, // argument
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.sequence_number(), Some(7));
+        assert_eq!(frame.args(), [Arg::Uxx(42)]);
+    }
+
+    #[test]
+    fn frames_lost() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "Hello, world!".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.sequence_numbers = true;
+
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&[0, 0, 254, 0, 0, 255, 0, 0, 2]);
+        //           sequence numbers ^^^        ^^^       ^
+
+        assert_eq!(stream_decoder.decode().unwrap().frames_lost(), 0);
+        assert_eq!(stream_decoder.decode().unwrap().frames_lost(), 0);
+        assert_eq!(stream_decoder.decode().unwrap().frames_lost(), 2);
+    }
+
+    #[test]
+    fn frames_lost_per_channel() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "Hello, world!".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.sequence_numbers = true;
+
+        // `defmt-rtt` numbers the frames of each up channel separately, and every channel is
+        // decoded on its own: interleaving frames between channels loses nothing
+        let mut channel0 = table.new_stream_decoder();
+        let mut channel1 = table.new_stream_decoder();
+        channel0.received(&[0, 0, 0]);
+        channel1.received(&[0, 0, 0, 0, 0, 1]);
+        channel0.received(&[0, 0, 1, 0, 0, 2]);
+
+        for _ in 0..3 {
+            assert_eq!(channel0.decode().unwrap().frames_lost(), 0);
+        }
+        for _ in 0..2 {
+            assert_eq!(channel1.decode().unwrap().frames_lost(), 0);
+        }
+    }
+
+    #[test]
+    fn varint() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "{=u32} {=i16} {=str} {=u8}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Info, "{=u64}".to_owned()),
+        ];
+
+        let mut table = test_table(entries);
+        table.encoding = Encoding::Varint;
+
+        let bytes = [
+            0, // index
+            0xac, 0x02, // 300
+            3,    // -2, zigzag-encoded
+            2, b'h', b'i', // length and bytes of the string
+            0xff, // u8s are not varints
+        ];
+
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(
+            frame.args(),
+            [
+                Arg::Uxx(300),
+                Arg::Ixx(-2),
+                Arg::Str("hi".to_owned()),
+                Arg::Uxx(255)
+            ]
+        );
+
+        // the value doesn't fit in a `u64`
+        let bytes = [
+            1, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f,
+        ];
+        assert_eq!(table.decode(&bytes), Err(DecodeError::Malformed));
+    }
+
+    #[test]
+    fn cobs() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "x={=u16}".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.encoding = Encoding::Cobs;
+
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&[0x00, 0x01, 0x01, 0x02, 0x2a, 0x01, 0x00]);
+        //                              ^^^^^^^^^^^^^^^^^^^^^^^^^^^^ [0, 0, 42, 0]
+
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.args(), [Arg::Uxx(42)]);
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::UnexpectedEof));
+    }
+
+    #[test]
+    fn hdlc_crc16() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "x={=u8}".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.encoding = Encoding::HdlcCrc16;
+
+        let fcs = stream::crc16(&[0, 0, 0x7e]).to_le_bytes();
+        let mut bytes = vec![0x7e, 0, 0, 0x7d, 0x5e, fcs[0], fcs[1], 0x7e];
+        // the same frame, corrupted
+        bytes.extend_from_slice(&[0, 0, 0x2a, fcs[0], fcs[1], 0x7e]);
+
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&bytes);
+
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.args(), [Arg::Uxx(0x7e)]);
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::Malformed));
+    }
+
+    #[test]
+    fn frame_crc() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "Hello, world!".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.encoding = Encoding::Rzcobs;
+        table.frame_crc = true;
+
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&[0x48, 0x0f, 0x73, 0x00]); // corrupted
+        stream_decoder.received(&[0x47, 0x0f, 0x73, 0x00]); // [0, 0] and its CRC
+
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.format(), "Hello, world!");
+        assert_eq!(frame.frames_corrupted(), 1);
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::UnexpectedEof));
+    }
+
+    #[test]
+    fn frame_crc_undecodable() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "{=str}".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.frame_crc = true;
+
+        // `[0, 0, 2, 0, 0, 0, b'h', b'i']` and its CRC; the length of the string is damaged to 9
+        // in the first frame, which can't be decoded then
+        let rzcobs: [&[u8]; 2] = [
+            &[9, 104, 59, 105, 119, 222, 120, 0],
+            &[2, 104, 59, 105, 119, 222, 120, 0],
+        ];
+        let cobs: [&[u8]; 2] = [
+            &[1, 1, 2, 9, 1, 1, 5, 104, 105, 119, 222, 0],
+            &[1, 1, 2, 2, 1, 1, 5, 104, 105, 119, 222, 0],
+        ];
+        for (encoding, [corrupted, intact]) in [(Encoding::Rzcobs, rzcobs), (Encoding::Cobs, cobs)]
+        {
+            table.encoding = encoding;
+            let mut stream_decoder = table.new_stream_decoder();
+            stream_decoder.received(corrupted);
+            stream_decoder.received(intact);
+            let frame = stream_decoder.decode().unwrap();
+            assert_eq!(frame.display_message().to_string(), "hi");
+            assert_eq!(frame.frames_corrupted(), 1);
+        }
+    }
+
+    #[test]
+    fn serialize_table() {
+        let entries = vec![
+            TableEntry::new_without_symbol(Tag::Info, "x={=?}".to_owned()),
+            TableEntry::new_without_symbol(Tag::Derived, "Foo {{ x: {=u8} }}".to_owned()),
+        ];
+
+        let mut table = test_table_with_timestamp(entries, "{=u8:us}");
+        table.encoding = Encoding::Cobs;
+        table.frame_crc = true;
+        table.fingerprint = Some(u64::MAX);
+        table.bitflags.insert(
+            BitflagsKey {
+                ident: "Flags".into(),
+                package: "app".into(),
+                disambig: "1234".into(),
+                crate_name: None,
+            },
+            vec![("A".into(), u128::MAX)],
+        );
+        table.enums.insert(
+            EnumKey {
+                ident: "Mode".into(),
+                package: "app".into(),
+                disambig: "5678".into(),
+                crate_name: None,
+            },
+            vec![("Off".into(), i128::MIN)],
+        );
+        let locations = Locations::from([(
+            0,
+            Location {
+                file: "src/main.rs".into(),
+                line: 42,
+                module: "app".into(),
+            },
+        )]);
+
+        let bytes = table.serialize(&locations);
+        let (new_table, new_locations) = Table::deserialize(&bytes).unwrap();
+        assert_eq!(new_table, table);
+        assert_eq!(new_locations.len(), 1);
+        assert_eq!(new_locations[&0].file, locations[&0].file);
+        assert_eq!(new_locations[&0].line, locations[&0].line);
+        assert_eq!(new_locations[&0].module, locations[&0].module);
+    }
+
+    #[test]
+    fn pending() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "Hello, world!".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.encoding = Encoding::Rzcobs;
+
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&[0x00, 0x7f, 0x00, 0x00, 0x7f]);
+        assert_eq!(stream_decoder.pending(), Some(4));
+
+        // the separators after a frame are popped along with it
+        stream_decoder.decode().unwrap();
+        assert_eq!(stream_decoder.pending(), Some(1));
+        assert_eq!(stream_decoder.decode(), Err(DecodeError::UnexpectedEof));
+    }
+
+    #[test]
+    fn frames_dropped() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "Hello, world!".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.sequence_numbers = true;
+
+        let mut stream_decoder = table.new_stream_decoder();
+        stream_decoder.received(&[0, 0, 1, 0xFF, 0xFF, 3, 0, 0, 0, 0, 0, 5]);
+        //                                 ^^^^^^^^^^ dropped frames report
+
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.frames_dropped(), None);
+
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.frames_dropped(), Some(3));
+        assert_eq!(frame.display(false).to_string(), "WARN 3 frames dropped");
+
+        // the frames reported as dropped are not reported as lost again
+        let frame = stream_decoder.decode().unwrap();
+        assert_eq!(frame.frames_lost(), 0);
+    }
+
+    #[test]
+    fn fingerprint() {
+        let entries = vec![TableEntry::new_without_symbol(
+            Tag::Info,
+            "Hello, world!".to_owned(),
+        )];
+
+        let mut table = test_table(entries);
+        table.fingerprint = Some(0x1234);
+
+        let bytes = [0xFE, 0xFF, 0x34, 0x12, 0, 0, 0, 0, 0, 0];
+        let (frame, consumed) = table.decode(&bytes).unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(frame.fingerprint(), Some(0x1234));
+        assert_eq!(
+            frame.display(false).to_string(),
+            "INFO firmware fingerprint 0x1234"
+        );
+
+        let bytes = [0xFE, 0xFF, 0x35, 0x12, 0, 0, 0, 0, 0, 0];
+        let frame = table.decode(&bytes).unwrap().0;
+        assert_eq!(frame.fingerprint(), Some(0x1235));
+        assert_eq!(frame.level(), Some(Level::Error));
+
+        let frame = table.decode(&[0, 0]).unwrap().0;
+        assert_eq!(frame.fingerprint(), None);
+    }
+
+    #[test]
+    fn observe_timestamps() {
+        use crate::log::format::{Formatter, FormatterConfig};
+
+        let entries = vec![TableEntry::new_without_symbol(Tag::Info, "x".to_owned())];
+        let table = test_table_with_timestamp(entries, "{=u8:us}");
+        let formatter =
+            Formatter::new(FormatterConfig::custom("{t} {dt} {s}").with_extended_timestamps());
+        let frame = |timestamp| table.decode(&[0, 0, timestamp]).unwrap().0;
+
+        formatter.observe(&frame(200));
+        assert_eq!(
+            formatter.format_frame(frame(200), None, None, None),
+            "0.000200 <dt> x"
+        );
+        // filtered out
+        formatter.observe(&frame(250));
+        // wraps around
+        formatter.observe(&frame(4));
+        assert_eq!(
+            formatter.format_frame(frame(4), None, None, None),
+            "0.000260 +0.000010 x"
+        );
+        // not observed
+        assert_eq!(
+            formatter.format_frame(frame(5), None, None, None),
+            "0.000005 <dt> x"
+        );
+    }
+
     #[test]
     fn option() {
         let mut entries = BTreeMap::new();
@@ -1102,7 +1910,11 @@ mod tests {
                 "{=u8:us}".to_owned(),
             )),
             bitflags: Default::default(),
+            enums: Default::default(),
             encoding: Encoding::Raw,
+            sequence_numbers: false,
+            frame_crc: false,
+            fingerprint: None,
         };
 
         let bytes = [
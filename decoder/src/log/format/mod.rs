@@ -1,10 +1,16 @@
 use super::{DefmtRecord, Payload};
-use crate::Frame;
+use crate::{clock::TimestampExtension, frame::format_timestamp, Frame, TargetTimestamp};
 use colored::{Color, ColoredString, Colorize, Styles};
 use dissimilar::Chunk;
 use log::{Level, Record as LogRecord};
 use regex::Regex;
-use std::{fmt::Write, path::Path};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+    path::Path,
+    sync::Mutex,
+};
+use time::{macros::format_description, OffsetDateTime};
 
 mod parser;
 
@@ -17,6 +23,12 @@ pub(super) enum LogMetadata {
     /// Prints the name of the crate where the log is coming from.
     CrateName,
 
+    /// `{core}` format specifier.
+    ///
+    /// Prints the name of the core the log is coming from, when decoding the logs of several
+    /// firmware images at once.
+    Core,
+
     /// `{f}` format specifier.
     ///
     /// This specifier may be repeated up to 255 times.
@@ -74,6 +86,26 @@ pub(super) enum LogMetadata {
     /// For a log printed with a timestamp 123456 ms, this prints "123456".
     Timestamp,
 
+    /// `{T}` format specifier.
+    ///
+    /// Prints the estimated date and time, in UTC, at which something was logged, from the
+    /// correlation of the timestamps of the target with the clock of the host.
+    /// This looks like "2024-05-07T12:34:56.789012Z".
+    WallClock,
+
+    /// `{dt}` format specifier.
+    ///
+    /// Prints the time between the timestamp of the previous log and the one of this log, also if
+    /// the previous log was filtered out. For logs printed with the timestamps 100 us and 223 us, this prints "+0.000123" for the
+    /// second one.
+    TimestampDelta,
+
+    /// `{dtl}` format specifier.
+    ///
+    /// Prints the time between the timestamp of the previous log from the same log statement and
+    /// the one of this log.
+    LocationTimestampDelta,
+
     /// Represents formats specified within nested curly brackets in the formatting string.
     NestedLogSegments(Vec<LogSegment>),
 }
@@ -207,7 +239,7 @@ impl Formatter {
         line: Option<u32>,
         module_path: Option<&str>,
     ) -> String {
-        let (timestamp, level) = super::timestamp_and_level_from_frame(&frame);
+        let payload = Payload::new(&frame);
 
         // HACK: use match instead of let, because otherwise compilation fails
         #[allow(clippy::match_single_binding)]
@@ -220,20 +252,93 @@ impl Formatter {
                     .line(line)
                     .build();
 
-                let record = DefmtRecord {
+                let mut record = DefmtRecord {
                     log_record,
-                    payload: Payload { level, timestamp },
+                    payload,
+                    // only the JSON logger prints them
+                    typed_args: Default::default(),
+                    wall_clock: None,
+                    deltas: Default::default(),
                 };
+                let observation = self.last_observation(&record.payload);
+                if let Some(observation) = observation {
+                    observation.apply(&mut record);
+                }
 
                 self.format(&record)
             }
         }
     }
 
+    /// Tracks the timestamp of a defmt frame, for the `{dt}` and `{dtl}` format specifiers and
+    /// for [`FormatterConfig::with_extended_timestamps`].
+    ///
+    /// This has to be called once for every decoded frame, in the order they were received, and
+    /// before [`Formatter::format_frame`] formats it. Frames which aren't formatted, e.g. because
+    /// they are filtered out, have to be observed as well, so that `{dt}` is the time since the
+    /// previous frame of the firmware.
+    pub fn observe(&self, frame: &Frame) {
+        if let Some(timestamp) = frame.target_timestamp() {
+            self.observe_timestamp(None, frame.index(), timestamp);
+        }
+    }
+
     /// Format the given [`DefmtRecord`] (which is an internal type).
     pub(super) fn format(&self, record: &DefmtRecord) -> String {
         self.formatter.format(&Record::Defmt(record))
     }
+
+    /// Tracks the timestamp of `record`, like [`Formatter::observe`], and sets its extended
+    /// timestamp and the time since the previous records.
+    pub(super) fn observe_record(&self, record: &mut DefmtRecord) {
+        let payload = &record.payload;
+        let Some(timestamp) = payload.timestamp_value else {
+            return;
+        };
+        self.observe_timestamp(payload.core.as_deref(), payload.index, timestamp)
+            .apply(record);
+    }
+
+    fn observe_timestamp(
+        &self,
+        core: Option<&str>,
+        index: u64,
+        sent: TargetTimestamp,
+    ) -> Observation {
+        let mut timestamps = self.formatter.timestamps.lock().unwrap();
+        let timestamps = timestamps.entry(core.map(str::to_string)).or_default();
+
+        let timestamp = match self.formatter.extend_timestamps {
+            true => timestamps.extension.extend(sent),
+            false => sent,
+        };
+        let observation = Observation {
+            index,
+            sent,
+            timestamp,
+            deltas: TimestampDeltas {
+                previous: TimeDelta::between(timestamps.previous, timestamp),
+                location: TimeDelta::between(
+                    timestamps.by_location.get(&index).copied(),
+                    timestamp,
+                ),
+            },
+        };
+        timestamps.previous = Some(timestamp);
+        timestamps.by_location.insert(index, timestamp);
+        timestamps.last = Some(observation);
+        observation
+    }
+
+    /// Returns what [`Formatter::observe`] found out about the frame of `payload`, if it was the
+    /// last one it observed.
+    fn last_observation(&self, payload: &Payload) -> Option<Observation> {
+        let timestamps = self.formatter.timestamps.lock().unwrap();
+        let observation = timestamps.get(&payload.core)?.last?;
+        let observed =
+            observation.index == payload.index && Some(observation.sent) == payload.timestamp_value;
+        observed.then_some(observation)
+    }
 }
 
 /// A formatter for host-generated frames
@@ -258,6 +363,96 @@ impl HostFormatter {
 #[derive(Debug)]
 struct InternalFormatter {
     format: Vec<LogSegment>,
+    extend_timestamps: bool,
+    /// The timestamps seen so far, for each core
+    timestamps: Mutex<BTreeMap<Option<String>, Timestamps>>,
+}
+
+/// The timestamps of a core that the formatter has seen so far
+#[derive(Debug, Default)]
+struct Timestamps {
+    extension: TimestampExtension,
+    previous: Option<TargetTimestamp>,
+    /// The previous timestamp of each log statement, by the index of its format string
+    by_location: BTreeMap<u64, TargetTimestamp>,
+    /// The last frame observed, for [`Formatter::format_frame`]
+    last: Option<Observation>,
+}
+
+/// What the formatter found out about the timestamp of a frame when it observed it
+#[derive(Clone, Copy, Debug)]
+struct Observation {
+    /// Index of the format string of the frame
+    index: u64,
+    /// The timestamp sent by the target
+    sent: TargetTimestamp,
+    /// The timestamp, extended to 64 bits if [`FormatterConfig::with_extended_timestamps`] is set
+    timestamp: TargetTimestamp,
+    deltas: TimestampDeltas,
+}
+
+impl Observation {
+    /// Sets the extended timestamp of `record`, and the time since the previous records.
+    fn apply(self, record: &mut DefmtRecord) {
+        let payload = &mut record.payload;
+        if let (true, Some(format)) = (self.timestamp != self.sent, &payload.timestamp_format) {
+            payload.timestamp = format_timestamp(format, self.timestamp.ticks);
+            payload.timestamp_value = Some(self.timestamp);
+        }
+        record.deltas = self.deltas;
+    }
+}
+
+/// The time since the previous records, for the `{dt}` and `{dtl}` format specifiers
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct TimestampDeltas {
+    previous: Option<TimeDelta>,
+    location: Option<TimeDelta>,
+}
+
+/// The time between two timestamps
+#[derive(Clone, Copy, Debug)]
+struct TimeDelta {
+    ticks: i128,
+    nanos_per_tick: Option<u64>,
+}
+
+impl TimeDelta {
+    /// Returns the time from `previous` to `timestamp`, if they have the same unit.
+    fn between(previous: Option<TargetTimestamp>, timestamp: TargetTimestamp) -> Option<Self> {
+        let previous =
+            previous.filter(|previous| previous.nanos_per_tick == timestamp.nanos_per_tick)?;
+        Some(Self {
+            ticks: i128::from(timestamp.ticks) - i128::from(previous.ticks),
+            nanos_per_tick: timestamp.nanos_per_tick,
+        })
+    }
+}
+
+impl fmt::Display for TimeDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.ticks < 0 { '-' } else { '+' };
+        let ticks = self.ticks.unsigned_abs();
+        let Some(nanos_per_tick) = self.nanos_per_tick else {
+            return write!(f, "{sign}{ticks}");
+        };
+
+        // print as many decimals as the timestamp has
+        let decimals = match nanos_per_tick {
+            0..=1_000 => 6,
+            1_001..=1_000_000 => 3,
+            _ => 0,
+        };
+        let nanos = ticks * u128::from(nanos_per_tick);
+        let (seconds, subseconds) = (nanos / 1_000_000_000, nanos % 1_000_000_000);
+        match decimals {
+            0 => write!(f, "{sign}{seconds}"),
+            _ => {
+                let subseconds = subseconds / 10u128.pow(9 - decimals);
+                write!(f, "{sign}{seconds}.{subseconds:0>0$}", decimals as usize)
+            }
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -375,6 +570,15 @@ pub struct FormatterConfig<'a> {
     /// Not all targets can supply a timestamp, and if not, it should be
     /// omitted.
     pub is_timestamp_available: bool,
+    /// If `true`, then the logs of the predefined formats start with the name of the core they
+    /// are coming from. Set by [`FormatterConfig::with_core`].
+    ///
+    /// This has no effect on a custom log-format string, which can use `{core}` instead.
+    with_core: bool,
+    /// If `true`, then timestamps narrower than 64 bits, like `{=u32:us}`, are extended to 64
+    /// bits when they wrap around, so they keep increasing. Set by
+    /// [`FormatterConfig::with_extended_timestamps`].
+    extend_timestamps: bool,
 }
 
 impl<'a> FormatterConfig<'a> {
@@ -387,6 +591,8 @@ impl<'a> FormatterConfig<'a> {
             format: FormatterFormat::from_string(format, true)
                 .unwrap_or(FormatterFormat::Custom(format)),
             is_timestamp_available: false,
+            with_core: false,
+            extend_timestamps: false,
         }
     }
 
@@ -397,6 +603,20 @@ impl<'a> FormatterConfig<'a> {
         self
     }
 
+    /// Modify a formatter configuration, setting the 'with_core' flag
+    /// to true.
+    pub fn with_core(mut self) -> Self {
+        self.with_core = true;
+        self
+    }
+
+    /// Modify a formatter configuration, setting the 'extend_timestamps' flag
+    /// to true.
+    pub fn with_extended_timestamps(mut self) -> Self {
+        self.extend_timestamps = true;
+        self
+    }
+
     /// Modify a formatter configuration, setting the 'with_location' flag
     /// to true.
     ///
@@ -431,6 +651,8 @@ impl InternalFormatter {
                         .to_string();
                 if source == Source::Host {
                     format.insert_str(0, "(HOST) ");
+                } else if config.with_core {
+                    format.insert_str(0, "[{core}] ");
                 }
 
                 format
@@ -442,6 +664,8 @@ impl InternalFormatter {
 
                 if source == Source::Host {
                     format.insert_str(0, "(HOST) ");
+                } else if config.with_core {
+                    format.insert_str(0, "[{core}] ");
                 }
 
                 format
@@ -468,7 +692,11 @@ impl InternalFormatter {
             }
         }
 
-        Self { format }
+        Self {
+            format,
+            extend_timestamps: config.extend_timestamps,
+            timestamps: Default::default(),
+        }
     }
 
     fn format(&self, record: &Record) -> String {
@@ -492,7 +720,15 @@ impl InternalFormatter {
         match &segment.metadata {
             LogMetadata::String(s) => s.to_string(),
             LogMetadata::Timestamp => self.build_timestamp(record, &segment.format),
+            LogMetadata::WallClock => self.build_wall_clock(record, &segment.format),
+            LogMetadata::TimestampDelta => {
+                self.build_timestamp_delta(record, &segment.format, false)
+            }
+            LogMetadata::LocationTimestampDelta => {
+                self.build_timestamp_delta(record, &segment.format, true)
+            }
             LogMetadata::CrateName => self.build_crate_name(record, &segment.format),
+            LogMetadata::Core => self.build_core(record, &segment.format),
             LogMetadata::FileName(n) => self.build_file_name(record, &segment.format, *n),
             LogMetadata::FilePath => self.build_file_path(record, &segment.format),
             LogMetadata::ModulePath => self.build_module_path(record, &segment.format),
@@ -511,7 +747,15 @@ impl InternalFormatter {
             let s = match &segment.metadata {
                 LogMetadata::String(s) => s.to_string(),
                 LogMetadata::Timestamp => self.build_timestamp(record, &segment.format),
+                LogMetadata::WallClock => self.build_wall_clock(record, &segment.format),
+                LogMetadata::TimestampDelta => {
+                    self.build_timestamp_delta(record, &segment.format, false)
+                }
+                LogMetadata::LocationTimestampDelta => {
+                    self.build_timestamp_delta(record, &segment.format, true)
+                }
                 LogMetadata::CrateName => self.build_crate_name(record, &segment.format),
+                LogMetadata::Core => self.build_core(record, &segment.format),
                 LogMetadata::FileName(n) => self.build_file_name(record, &segment.format, *n),
                 LogMetadata::FilePath => self.build_file_path(record, &segment.format),
                 LogMetadata::ModulePath => self.build_module_path(record, &segment.format),
@@ -550,6 +794,51 @@ impl InternalFormatter {
         )
     }
 
+    fn build_wall_clock(&self, record: &Record, format: &LogFormat) -> String {
+        let date_time = match record {
+            Record::Defmt(record) => record
+                .wall_clock()
+                .and_then(|nanos| OffsetDateTime::from_unix_timestamp_nanos(nanos.into()).ok()),
+            Record::Host(_) => None,
+        };
+        let s = match date_time {
+            Some(date_time) => date_time
+                .format(format_description!(
+                    "[year]-[month]-[day]T[hour]:[minute]:[second].[subsecond digits:6]Z"
+                ))
+                .unwrap(),
+            None => "<wall clock>".to_string(),
+        };
+
+        build_formatted_string(
+            s.as_str(),
+            format,
+            0,
+            get_log_level_of_record(record),
+            format.color,
+        )
+    }
+
+    fn build_timestamp_delta(&self, record: &Record, format: &LogFormat, location: bool) -> String {
+        let delta = match record {
+            Record::Defmt(record) if location => record.deltas.location,
+            Record::Defmt(record) => record.deltas.previous,
+            Record::Host(_) => None,
+        };
+        let s = match delta {
+            Some(delta) => delta.to_string(),
+            None => "<dt>".to_string(),
+        };
+
+        build_formatted_string(
+            s.as_str(),
+            format,
+            0,
+            get_log_level_of_record(record),
+            format.color,
+        )
+    }
+
     fn build_log_level(&self, record: &Record, format: &LogFormat) -> String {
         let s = match get_log_level_of_record(record) {
             Some(level) => level.to_string(),
@@ -645,6 +934,16 @@ impl InternalFormatter {
         build_formatted_string(s, format, 0, get_log_level_of_record(record), format.color)
     }
 
+    fn build_core(&self, record: &Record, format: &LogFormat) -> String {
+        let s = match record {
+            Record::Defmt(record) => record.core(),
+            Record::Host(_) => None,
+        }
+        .unwrap_or("<core>");
+
+        build_formatted_string(s, format, 0, get_log_level_of_record(record), format.color)
+    }
+
     fn build_line_number(&self, record: &Record, format: &LogFormat) -> String {
         let s = match record {
             Record::Defmt(record) => record.line(),
@@ -838,7 +1137,10 @@ fn build_formatted_string(
 fn format_has_timestamp(segments: &[LogSegment]) -> bool {
     for segment in segments {
         match &segment.metadata {
-            LogMetadata::Timestamp => return true,
+            LogMetadata::Timestamp
+            | LogMetadata::WallClock
+            | LogMetadata::TimestampDelta
+            | LogMetadata::LocationTimestampDelta => return true,
             LogMetadata::NestedLogSegments(s) => {
                 if format_has_timestamp(s) {
                     return true;
@@ -892,4 +1194,19 @@ mod tests {
         let string_without_styles = string_excluding_ansi(&s);
         assert_eq!(string_without_styles, "      test");
     }
+
+    #[test]
+    fn test_time_delta() {
+        let delta = |ticks, nanos_per_tick| {
+            TimeDelta {
+                ticks,
+                nanos_per_tick,
+            }
+            .to_string()
+        };
+        assert_eq!(delta(123, Some(1_000)), "+0.000123");
+        assert_eq!(delta(1_500, Some(1_000_000)), "+1.500");
+        assert_eq!(delta(-2, Some(1_000_000_000)), "-2");
+        assert_eq!(delta(42, None), "+42");
+    }
 }
@@ -193,12 +193,16 @@ fn parse_metadata(input: &str) -> IResult<&str, IntermediateOutput, ()> {
     let mut parse_type = map_res(take_while(char::is_alphabetic), move |s| {
         let metadata = match s {
             "c" => LogMetadata::CrateName,
+            "core" => LogMetadata::Core,
             "F" => LogMetadata::FilePath,
             "l" => LogMetadata::LineNumber,
             "s" => LogMetadata::Log,
             "L" => LogMetadata::LogLevel,
             "m" => LogMetadata::ModulePath,
             "t" => LogMetadata::Timestamp,
+            "T" => LogMetadata::WallClock,
+            "dt" => LogMetadata::TimestampDelta,
+            "dtl" => LogMetadata::LocationTimestampDelta,
             _ => {
                 if !s.is_empty() && s == "f".repeat(s.len()) {
                     LogMetadata::FileName(s.len() as u8)
@@ -523,6 +527,43 @@ mod tests {
         assert_eq!(result, Ok(("", LogSegment::new(LogMetadata::Timestamp))));
     }
 
+    #[test]
+    fn test_parse_wall_clock_argument() {
+        let result = parse_argument::<false>("{T}");
+        assert_eq!(result, Ok(("", LogSegment::new(LogMetadata::WallClock))));
+    }
+
+    #[test]
+    fn test_parse_timestamp_delta_arguments() {
+        let result = parse("{dt:>10} {dtl} {s}");
+        let expected_output = vec![
+            LogSegment::new(LogMetadata::TimestampDelta)
+                .with_width(10)
+                .with_alignment(Alignment::Right)
+                .with_padding(Padding::Space),
+            LogSegment::new(LogMetadata::String(" ".to_string())),
+            LogSegment::new(LogMetadata::LocationTimestampDelta),
+            LogSegment::new(LogMetadata::String(" ".to_string())),
+            LogSegment::new(LogMetadata::Log),
+        ];
+        assert_eq!(result, Ok(expected_output));
+    }
+
+    #[test]
+    fn test_parse_core_argument() {
+        let result = parse("[{core:>4}] {s}");
+        let expected_output = vec![
+            LogSegment::new(LogMetadata::String("[".to_string())),
+            LogSegment::new(LogMetadata::Core)
+                .with_width(4)
+                .with_alignment(Alignment::Right)
+                .with_padding(Padding::Space),
+            LogSegment::new(LogMetadata::String("] ".to_string())),
+            LogSegment::new(LogMetadata::Log),
+        ];
+        assert_eq!(result, Ok(expected_output));
+    }
+
     #[test]
     fn test_parse_argument_with_color() {
         let result = parse_log_segment::<false>("t:werror");
@@ -1,17 +1,17 @@
-use defmt_json_schema::v1::{JsonFrame, Location, ModulePath, SCHEMA_VERSION};
+use defmt_json_schema::v2::{JsonFrame, Location, ModulePath, SCHEMA_VERSION};
 use log::{Log, Metadata, Record};
-use time::OffsetDateTime;
 
 use std::io::{self, Write};
 
 use super::{
     format::{Formatter, HostFormatter},
-    DefmtRecord, StdoutLogger,
+    host_timestamp, Clocks, DefmtRecord, StdoutLogger,
 };
 
 pub(crate) struct JsonLogger {
     should_log: Box<dyn Fn(&Metadata) -> bool + Sync + Send>,
     host_logger: StdoutLogger,
+    clocks: Clocks,
 }
 
 impl Log for JsonLogger {
@@ -20,20 +20,23 @@ impl Log for JsonLogger {
     }
 
     fn log(&self, record: &Record) {
-        if !self.enabled(record.metadata()) {
-            return;
-        }
+        let enabled = self.enabled(record.metadata());
+        if let Some(mut record) = DefmtRecord::new(record) {
+            // also for the records which aren't printed, so that `{dt}` is the time since the
+            // previous frame of the firmware
+            self.host_logger.observe_record(&mut record);
+            if record.is_observe_only() || !enabled {
+                return;
+            }
 
-        if let Some(record) = DefmtRecord::new(record) {
             // defmt goes to stdout, since it's the primary output produced by this tool.
             let mut sink = io::stdout().lock();
 
-            let host_timestamp = OffsetDateTime::now_utc()
-                .unix_timestamp_nanos()
-                .min(i64::MAX as i128) as i64;
+            let host_timestamp = host_timestamp();
+            self.clocks.correlate(&mut record, host_timestamp);
             serde_json::to_writer(&mut sink, &create_json_frame(record, host_timestamp)).ok();
             writeln!(sink).ok();
-        } else {
+        } else if enabled {
             // non-defmt logs go to stderr
             let sink = io::stderr().lock();
             self.host_logger.print_host_record(record, sink);
@@ -52,6 +55,7 @@ impl JsonLogger {
         Box::new(Self {
             should_log: Box::new(should_log),
             host_logger: StdoutLogger::new_unboxed(formatter, host_formatter, |_| true),
+            clocks: Clocks::default(),
         })
     }
 
@@ -66,6 +70,10 @@ impl JsonLogger {
 fn create_json_frame(record: DefmtRecord, host_timestamp: i64) -> JsonFrame {
     JsonFrame {
         data: record.args().to_string(),
+        format: record.format().to_string(),
+        index: record.index(),
+        args: record.decoded_args().to_vec(),
+        fields: record.fields().clone(),
         host_timestamp,
         level: record.level(),
         location: Location {
@@ -74,6 +82,8 @@ fn create_json_frame(record: DefmtRecord, host_timestamp: i64) -> JsonFrame {
             module_path: create_module_path(record.module_path()),
         },
         target_timestamp: record.timestamp().to_string(),
+        wall_clock: record.wall_clock(),
+        core: record.core().map(|core| core.to_string()),
     }
 }
 
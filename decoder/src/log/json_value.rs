@@ -0,0 +1,219 @@
+//! Conversion of decoded arguments into the typed [`Value`]s of the JSON output.
+
+use std::collections::BTreeMap;
+
+use defmt_json_schema::v2::Value;
+use defmt_parser::{Fragment, ParserMode};
+
+use crate::Arg;
+
+pub(super) fn from_arg(arg: &Arg) -> Value {
+    match arg {
+        Arg::Bool(x) => Value::Bool(*x),
+        Arg::F32(x) => Value::F32(*x),
+        Arg::F64(x) => Value::F64(*x),
+        Arg::Uxx(x) => Value::Uint(*x),
+        Arg::Ixx(x) => Value::Int(*x),
+        Arg::Str(x) | Arg::Preformatted(x) => Value::Str(x.clone()),
+        Arg::IStr(x) => Value::Str(x.to_string()),
+        Arg::Format { format, args } => from_format(format, args),
+        Arg::FormatSlice { elements } => Value::List(
+            elements
+                .iter()
+                .map(|element| from_format(element.format, &element.args))
+                .collect(),
+        ),
+        Arg::FormatSequence { args } => Value::Format {
+            format: "{=?}".repeat(args.len()),
+            args: args.iter().map(from_arg).collect(),
+        },
+        Arg::Slice(x) => Value::Bytes(x.clone()),
+        Arg::Char(x) => Value::Char(*x),
+    }
+}
+
+fn from_format(format: &str, args: &[Arg]) -> Value {
+    // the `Format` implementations of primitives, e.g. `"{=u8}"`, don't add anything to the value
+    if let ([arg], Ok([Fragment::Parameter(_)])) = (
+        args,
+        defmt_parser::parse(format, ParserMode::ForwardsCompatible).as_deref(),
+    ) {
+        return from_arg(arg);
+    }
+
+    let args = args.iter().map(from_arg).collect::<Vec<_>>();
+    match derived(format, &args) {
+        Some(value) => value,
+        None => Value::Format {
+            format: format.to_string(),
+            args,
+        },
+    }
+}
+
+/// Recovers the structure of a value from the format string `#[derive(Format)]` generates for it.
+///
+/// Those are `Name`, `Name({=u8}, {=?})` and `Name { x: {=u8:?}, y: {=?:?} }`, where `Name` is
+/// the name of the struct or enum variant. Returns `None` if `format` doesn't look like that.
+fn derived(format: &str, args: &[Value]) -> Option<Value> {
+    let fragments = defmt_parser::parse(format, ParserMode::ForwardsCompatible).ok()?;
+
+    // derived format strings alternate between literals and parameters, which use the arguments
+    // in order
+    let mut literals = vec![];
+    for (i, fragment) in fragments.iter().enumerate() {
+        match (i % 2, fragment) {
+            (0, Fragment::Literal(literal)) => literals.push(&**literal),
+            (1, Fragment::Parameter(param)) if param.index == i / 2 => {}
+            _ => return None,
+        }
+    }
+    if literals.len() != args.len() + 1 {
+        return None;
+    }
+    let (head, rest) = literals.split_first()?;
+    if args.is_empty() {
+        return is_name(head).then(|| Value::Unit {
+            name: head.to_string(),
+        });
+    }
+    let (separators, tail) = rest.split_at(rest.len() - 1);
+
+    if let Some((name, first)) = head.split_once(" { ") {
+        let mut names = vec![first.strip_suffix(": ")?];
+        for separator in separators {
+            names.push(separator.strip_prefix(", ")?.strip_suffix(": ")?);
+        }
+        if !is_name(name) || !names.iter().all(|name| is_name(name)) || tail != [" }"] {
+            return None;
+        }
+        let fields = names
+            .into_iter()
+            .map(str::to_string)
+            .zip(args.iter().cloned())
+            .collect::<BTreeMap<_, _>>();
+        return Some(Value::Struct {
+            name: name.to_string(),
+            fields,
+        });
+    }
+
+    let name = head.strip_suffix('(')?;
+    if !is_name(name) || separators.iter().any(|s| *s != ", ") || tail != [")"] {
+        return None;
+    }
+    Some(Value::Tuple {
+        name: name.to_string(),
+        fields: args.to_vec(),
+    })
+}
+
+/// Whether `s` is a Rust identifier, possibly a raw one.
+fn is_name(s: &str) -> bool {
+    let s = s.strip_prefix("r#").unwrap_or(s);
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format<'t>(format: &'t str, args: Vec<Arg<'t>>) -> Arg<'t> {
+        Arg::Format { format, args }
+    }
+
+    #[test]
+    fn primitives() {
+        assert_eq!(from_arg(&Arg::Uxx(42)), Value::Uint(42));
+        assert_eq!(
+            from_arg(&format("{=i8}", vec![Arg::Ixx(-1)])),
+            Value::Int(-1)
+        );
+        assert_eq!(from_arg(&Arg::Slice(vec![1, 2])), Value::Bytes(vec![1, 2]));
+    }
+
+    #[test]
+    fn derived() {
+        assert_eq!(
+            from_arg(&format(
+                "Foo {{ x: {=u8:?}, r#type: {=?:?} }}",
+                vec![Arg::Uxx(1), format("{=bool}", vec![Arg::Bool(true)])]
+            )),
+            Value::Struct {
+                name: "Foo".into(),
+                fields: BTreeMap::from([
+                    ("x".into(), Value::Uint(1)),
+                    ("r#type".into(), Value::Bool(true)),
+                ]),
+            }
+        );
+        assert_eq!(
+            from_arg(&format("Some({=?})", vec![format("None", vec![])])),
+            Value::Tuple {
+                name: "Some".into(),
+                fields: vec![Value::Unit {
+                    name: "None".into()
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn not_derived() {
+        assert_eq!(
+            from_arg(&format("x={=u8}", vec![Arg::Uxx(1)])),
+            Value::Format {
+                format: "x={=u8}".into(),
+                args: vec![Value::Uint(1)],
+            }
+        );
+        assert_eq!(
+            from_arg(&format(
+                "Foo {{ x: {1=u8}, y: {0=u8} }}",
+                vec![Arg::Uxx(1), Arg::Uxx(2)]
+            )),
+            Value::Format {
+                format: "Foo {{ x: {1=u8}, y: {0=u8} }}".into(),
+                args: vec![Value::Uint(1), Value::Uint(2)],
+            }
+        );
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let value = Value::List(vec![Value::Uint(u128::MAX), Value::Int(i128::MIN)]);
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&json).unwrap(), value);
+    }
+
+    #[test]
+    fn serde_non_finite_floats() {
+        let value = Value::List(vec![
+            Value::F32(1.5),
+            Value::F32(f32::INFINITY),
+            Value::F64(f64::NEG_INFINITY),
+        ]);
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"list","value":[{"type":"f32","value":1.5},{"type":"f32","value":"inf"},{"type":"f64","value":"-inf"}]}"#
+        );
+        assert_eq!(serde_json::from_str::<Value>(&json).unwrap(), value);
+
+        // NaNs aren't equal to anything, so compare their bits
+        for (nan, expected) in [
+            (f64::NAN, "NaN"),
+            (f64::from_bits(0xfff0_0000_0000_0001), "-NaN(0x1)"),
+        ] {
+            let json = serde_json::to_string(&Value::F64(nan)).unwrap();
+            assert_eq!(json, format!(r#"{{"type":"f64","value":"{expected}"}}"#));
+            match serde_json::from_str::<Value>(&json).unwrap() {
+                Value::F64(x) => assert_eq!(x.to_bits(), nan.to_bits()),
+                value => panic!("unexpected {value:?}"),
+            }
+        }
+        assert!(serde_json::from_str::<Value>(r#"{"type":"f32","value":"NaN(0x0)"}"#).is_err());
+    }
+}
@@ -10,22 +10,33 @@
 
 pub mod format;
 mod json_logger;
+mod json_value;
 mod stdout_logger;
 
-use std::fmt;
+use std::{cell::RefCell, collections::BTreeMap, fmt, sync::Mutex};
 
+use defmt_json_schema::v2::Value;
 use log::{Level, LevelFilter, Log, Metadata, Record as LogRecord};
 use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 
 use self::{
-    format::{Formatter, HostFormatter},
+    format::{Formatter, HostFormatter, TimestampDeltas},
     json_logger::JsonLogger,
     stdout_logger::StdoutLogger,
 };
-use crate::Frame;
+use crate::{ClockCorrelation, Frame, TargetTimestamp};
 
 const DEFMT_TARGET_MARKER: &str = "defmt@";
 
+thread_local! {
+    /// The typed arguments and fields of the frame being logged by [`log_payload`].
+    ///
+    /// They are passed next to the `log` record, instead of being serialized into its target
+    /// like the [`Payload`], since only the JSON logger needs them.
+    static TYPED_ARGS: RefCell<Option<TypedArgs>> = const { RefCell::new(None) };
+}
+
 /// Logs a defmt frame using the `log` facade.
 pub fn log_defmt(
     frame: &Frame<'_>,
@@ -33,14 +44,41 @@ pub fn log_defmt(
     line: Option<u32>,
     module_path: Option<&str>,
 ) {
-    let (timestamp, level) = timestamp_and_level_from_frame(frame);
+    log_payload(Payload::new(frame), frame, file, line, module_path)
+}
+
+/// Logs a defmt frame that was sent by `core` using the `log` facade.
+///
+/// This is meant for decoding the logs of several firmware images at once, e.g. one per core of a
+/// multi-core chip. The `{core}` format specifier prints `core`.
+pub fn log_defmt_from_core(
+    frame: &Frame<'_>,
+    core: &str,
+    file: Option<&str>,
+    line: Option<u32>,
+    module_path: Option<&str>,
+) {
+    let payload = Payload {
+        core: Some(core.to_string()),
+        ..Payload::new(frame)
+    };
+    log_payload(payload, frame, file, line, module_path)
+}
 
+fn log_payload(
+    payload: Payload,
+    frame: &Frame<'_>,
+    file: Option<&str>,
+    line: Option<u32>,
+    module_path: Option<&str>,
+) {
     let target = format!(
         "{}{}",
         DEFMT_TARGET_MARKER,
-        serde_json::to_value(Payload { timestamp, level }).unwrap()
+        serde_json::to_value(payload).unwrap()
     );
 
+    TYPED_ARGS.with(|typed| *typed.borrow_mut() = Some(TypedArgs::new(frame)));
     log::logger().log(
         &LogRecord::builder()
             .args(format_args!("{}", frame.display_message()))
@@ -51,9 +89,40 @@ pub fn log_defmt(
             .line(line)
             .build(),
     );
+    // the logger didn't take them if it filtered the record out
+    TYPED_ARGS.with(|typed| typed.borrow_mut().take());
+}
+
+/// Lets the logger track the timestamp of a defmt frame which isn't logged, e.g. because it was
+/// filtered out, so that the `{dt}` format specifier prints the time since the previous frame of
+/// the firmware, logged or not.
+pub fn observe_defmt(frame: &Frame<'_>) {
+    observe_payload(Payload::new(frame))
 }
 
-/// Determines whether `metadata` belongs to a log record produced by [`log_defmt`].
+/// Like [`observe_defmt`], for a defmt frame that was sent by `core`, see [`log_defmt_from_core`].
+pub fn observe_defmt_from_core(frame: &Frame<'_>, core: &str) {
+    observe_payload(Payload {
+        core: Some(core.to_string()),
+        ..Payload::new(frame)
+    })
+}
+
+fn observe_payload(payload: Payload) {
+    let payload = Payload {
+        observe_only: true,
+        ..payload
+    };
+    let target = format!(
+        "{}{}",
+        DEFMT_TARGET_MARKER,
+        serde_json::to_value(payload).unwrap()
+    );
+    log::logger().log(&LogRecord::builder().target(&target).build());
+}
+
+/// Determines whether `metadata` belongs to a log record produced by [`log_defmt`] or
+/// [`log_defmt_from_core`].
 pub fn is_defmt_frame(metadata: &Metadata) -> bool {
     metadata.target().starts_with(DEFMT_TARGET_MARKER)
 }
@@ -62,6 +131,10 @@ pub fn is_defmt_frame(metadata: &Metadata) -> bool {
 struct DefmtRecord<'a> {
     log_record: &'a LogRecord<'a>,
     payload: Payload,
+    typed_args: TypedArgs,
+    /// Estimated Unix timestamp, in nanoseconds, at which the frame was logged
+    wall_clock: Option<i64>,
+    deltas: TimestampDeltas,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -80,10 +153,71 @@ pub struct DefmtLoggerConfig {
 struct Payload {
     level: Option<Level>,
     timestamp: String,
+    format: String,
+    index: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    core: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp_value: Option<TargetTimestamp>,
+    /// Format of the timestamp, if it has a `timestamp_value`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp_format: Option<String>,
+    /// Whether the frame is only observed, see [`observe_defmt`]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    observe_only: bool,
+}
+
+impl Payload {
+    fn new(frame: &Frame<'_>) -> Self {
+        let timestamp = frame
+            .display_timestamp()
+            .map(|ts| ts.to_string())
+            .unwrap_or_default();
+        let timestamp_value = frame.target_timestamp();
+        let level = frame.level().map(|level| match level {
+            crate::Level::Trace => Level::Trace,
+            crate::Level::Debug => Level::Debug,
+            crate::Level::Info => Level::Info,
+            crate::Level::Warn => Level::Warn,
+            crate::Level::Error => Level::Error,
+        });
+        Self {
+            level,
+            timestamp,
+            format: frame.format().to_string(),
+            index: frame.index(),
+            core: None,
+            timestamp_value,
+            timestamp_format: timestamp_value
+                .and(frame.timestamp_format())
+                .map(str::to_string),
+            observe_only: false,
+        }
+    }
+}
+
+/// The arguments and structured fields of a frame, as typed values.
+#[derive(Default)]
+struct TypedArgs {
+    args: Vec<Value>,
+    fields: BTreeMap<String, Value>,
+}
+
+impl TypedArgs {
+    fn new(frame: &Frame<'_>) -> Self {
+        Self {
+            args: frame.args().iter().map(json_value::from_arg).collect(),
+            fields: frame
+                .fields()
+                .iter()
+                .map(|(name, arg)| (name.to_string(), json_value::from_arg(arg)))
+                .collect(),
+        }
+    }
 }
 
 impl<'a> DefmtRecord<'a> {
-    /// If `record` was produced by [`log_defmt`], returns the corresponding `DefmtRecord`.
+    /// If `record` was produced by [`log_defmt`] or [`log_defmt_from_core`], returns the corresponding `DefmtRecord`.
     pub fn new(log_record: &'a LogRecord<'a>) -> Option<Self> {
         let target = log_record.metadata().target();
         target
@@ -91,6 +225,11 @@ impl<'a> DefmtRecord<'a> {
             .map(|payload| Self {
                 log_record,
                 payload: serde_json::from_str(payload).expect("malformed 'payload'"),
+                typed_args: TYPED_ARGS
+                    .with(|typed| typed.borrow_mut().take())
+                    .unwrap_or_default(),
+                wall_clock: None,
+                deltas: Default::default(),
             })
     }
 
@@ -103,6 +242,43 @@ impl<'a> DefmtRecord<'a> {
         self.payload.level
     }
 
+    /// Returns the format string of the frame, without its structured fields.
+    pub fn format(&self) -> &str {
+        &self.payload.format
+    }
+
+    /// Returns the index of the format string of the frame.
+    pub fn index(&self) -> u64 {
+        self.payload.index
+    }
+
+    /// Returns the decoded arguments of the frame.
+    pub fn decoded_args(&self) -> &[Value] {
+        &self.typed_args.args
+    }
+
+    /// Returns the structured fields of the frame.
+    pub fn fields(&self) -> &BTreeMap<String, Value> {
+        &self.typed_args.fields
+    }
+
+    /// Returns the name of the core that sent the frame, if it was logged with
+    /// [`log_defmt_from_core`].
+    pub fn core(&self) -> Option<&str> {
+        self.payload.core.as_deref()
+    }
+
+    /// Returns whether the frame was passed to [`observe_defmt`] instead of being logged.
+    fn is_observe_only(&self) -> bool {
+        self.payload.observe_only
+    }
+
+    /// Returns the estimated Unix timestamp, in nanoseconds, at which the frame was logged on the
+    /// target, if the logger correlated its timestamp with the clock of the host.
+    pub fn wall_clock(&self) -> Option<i64> {
+        self.wall_clock
+    }
+
     pub fn args(&self) -> &fmt::Arguments<'a> {
         self.log_record.args()
     }
@@ -120,6 +296,31 @@ impl<'a> DefmtRecord<'a> {
     }
 }
 
+/// Correlates the timestamps of each core with the clock of the host.
+#[derive(Default)]
+struct Clocks(Mutex<BTreeMap<Option<String>, ClockCorrelation>>);
+
+impl Clocks {
+    /// Feeds the timestamp of `record`, which was received at `host_timestamp`, to the
+    /// correlation, and sets the wall-clock estimate of `record`.
+    fn correlate(&self, record: &mut DefmtRecord, host_timestamp: i64) {
+        let Some(timestamp) = record.payload.timestamp_value else {
+            return;
+        };
+        let mut clocks = self.0.lock().unwrap();
+        let clock = clocks.entry(record.payload.core.clone()).or_default();
+        clock.observe(timestamp, host_timestamp);
+        record.wall_clock = clock.estimate(timestamp);
+    }
+}
+
+/// Returns the current Unix timestamp in nanoseconds.
+fn host_timestamp() -> i64 {
+    OffsetDateTime::now_utc()
+        .unix_timestamp_nanos()
+        .min(i64::MAX as i128) as i64
+}
+
 /// Initializes a `log` sink that handles defmt frames.
 ///
 /// Defmt frames will be printed to stdout, other logs to stderr.
@@ -142,18 +343,3 @@ pub fn init_logger(
     alterable_logger::set_boxed_logger(logger);
     alterable_logger::set_max_level(LevelFilter::Trace);
 }
-
-fn timestamp_and_level_from_frame(frame: &Frame<'_>) -> (String, Option<Level>) {
-    let timestamp = frame
-        .display_timestamp()
-        .map(|ts| ts.to_string())
-        .unwrap_or_default();
-    let level = frame.level().map(|level| match level {
-        crate::Level::Trace => Level::Trace,
-        crate::Level::Debug => Level::Debug,
-        crate::Level::Info => Level::Info,
-        crate::Level::Warn => Level::Warn,
-        crate::Level::Error => Level::Error,
-    });
-    (timestamp, level)
-}
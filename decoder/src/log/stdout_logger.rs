@@ -3,13 +3,14 @@ use std::io::{self, StderrLock, StdoutLock, Write};
 
 use super::{
     format::{Formatter, HostFormatter},
-    DefmtRecord,
+    host_timestamp, Clocks, DefmtRecord,
 };
 
 pub(crate) struct StdoutLogger {
     formatter: Formatter,
     host_formatter: HostFormatter,
     should_log: Box<dyn Fn(&Metadata) -> bool + Sync + Send>,
+    clocks: Clocks,
 }
 
 impl Log for StdoutLogger {
@@ -18,12 +19,16 @@ impl Log for StdoutLogger {
     }
 
     fn log(&self, record: &LogRecord) {
-        if !self.enabled(record.metadata()) {
-            return;
-        }
-
+        let enabled = self.enabled(record.metadata());
         match DefmtRecord::new(record) {
-            Some(record) => {
+            Some(mut record) => {
+                // also for the records which aren't printed, so that `{dt}` is the time since the
+                // previous frame of the firmware
+                self.formatter.observe_record(&mut record);
+                if record.is_observe_only() || !enabled {
+                    return;
+                }
+                self.clocks.correlate(&mut record, host_timestamp());
                 // defmt goes to stdout, since it's the primary output produced by this tool.
                 let sink = io::stdout().lock();
                 if record.level().is_some() {
@@ -32,11 +37,12 @@ impl Log for StdoutLogger {
                     self.print_defmt_record_without_format(record, sink);
                 }
             }
-            None => {
+            None if enabled => {
                 // non-defmt logs go to stderr
                 let sink = io::stderr().lock();
                 self.print_host_record(record, sink);
             }
+            None => {}
         }
     }
 
@@ -61,9 +67,14 @@ impl StdoutLogger {
             formatter,
             host_formatter,
             should_log: Box::new(should_log),
+            clocks: Clocks::default(),
         }
     }
 
+    pub(super) fn observe_record(&self, record: &mut DefmtRecord) {
+        self.formatter.observe_record(record);
+    }
+
     fn print_defmt_record(&self, record: DefmtRecord, mut sink: StdoutLock) {
         let s = self.formatter.format(&record);
         writeln!(sink, "{s}").ok();
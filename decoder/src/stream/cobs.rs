@@ -0,0 +1,64 @@
+use super::{decode_framed, received_framed, SequenceTracker, StreamDecoder};
+use crate::{DecodeError, Frame, Table};
+
+/// Decode a full message.
+///
+/// `data` must be a full COBS encoded message. Decoding partial
+/// messages is not possible. `data` must NOT include any `0x00` separator byte.
+pub fn cobs_decode(mut data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut res = vec![];
+    while let Some((&code, rest)) = data.split_first() {
+        if code == 0 {
+            return Err(DecodeError::Malformed);
+        }
+
+        let len = usize::from(code) - 1;
+        let block = rest.get(..len).ok_or(DecodeError::Malformed)?;
+        res.extend_from_slice(block);
+        data = &rest[len..];
+
+        // a block which is not full stands for a zero, except at the end of the message
+        if code != 0xff && !data.is_empty() {
+            res.push(0);
+        }
+    }
+
+    Ok(res)
+}
+
+pub struct Cobs<'a> {
+    table: &'a Table,
+    raw: Vec<u8>,
+    sequence: SequenceTracker,
+}
+
+impl<'a> Cobs<'a> {
+    pub fn new(table: &'a Table) -> Self {
+        Self {
+            table,
+            raw: Vec::new(),
+            sequence: SequenceTracker::default(),
+        }
+    }
+}
+
+impl StreamDecoder for Cobs<'_> {
+    fn received(&mut self, data: &[u8]) {
+        received_framed(&mut self.raw, data, 0);
+    }
+
+    fn decode(&mut self) -> Result<Frame<'_>, DecodeError> {
+        decode_framed(
+            self.table,
+            &mut self.raw,
+            &mut self.sequence,
+            0,
+            cobs_decode,
+            false,
+        )
+    }
+
+    fn pending(&self) -> Option<usize> {
+        Some(self.raw.len())
+    }
+}
@@ -0,0 +1,71 @@
+use super::{crc16, decode_framed, received_framed, SequenceTracker, StreamDecoder};
+use crate::{DecodeError, Frame, Table};
+
+/// Frame delimiter
+const FLAG: u8 = 0x7e;
+/// Escapes the next byte, which is transmitted XORed with `ESCAPE_XOR`
+const ESCAPE: u8 = 0x7d;
+const ESCAPE_XOR: u8 = 0x20;
+
+/// Decode a full message and check its frame check sequence.
+///
+/// `data` must be a full HDLC encoded message. Decoding partial
+/// messages is not possible. `data` must NOT include any `0x7E` flag byte.
+pub fn hdlc_decode(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut res = Vec::with_capacity(data.len());
+    let mut data = data.iter();
+    while let Some(&x) = data.next() {
+        match x {
+            ESCAPE => res.push(data.next().ok_or(DecodeError::Malformed)? ^ ESCAPE_XOR),
+            _ => res.push(x),
+        }
+    }
+
+    let Some(len) = res.len().checked_sub(2) else {
+        return Err(DecodeError::Malformed);
+    };
+    let fcs = u16::from_le_bytes([res[len], res[len + 1]]);
+    if crc16(&res[..len]) != fcs {
+        return Err(DecodeError::Malformed);
+    }
+
+    res.truncate(len);
+    Ok(res)
+}
+
+pub struct Hdlc<'a> {
+    table: &'a Table,
+    raw: Vec<u8>,
+    sequence: SequenceTracker,
+}
+
+impl<'a> Hdlc<'a> {
+    pub fn new(table: &'a Table) -> Self {
+        Self {
+            table,
+            raw: Vec::new(),
+            sequence: SequenceTracker::default(),
+        }
+    }
+}
+
+impl StreamDecoder for Hdlc<'_> {
+    fn received(&mut self, data: &[u8]) {
+        received_framed(&mut self.raw, data, FLAG);
+    }
+
+    fn decode(&mut self) -> Result<Frame<'_>, DecodeError> {
+        decode_framed(
+            self.table,
+            &mut self.raw,
+            &mut self.sequence,
+            FLAG,
+            hdlc_decode,
+            false,
+        )
+    }
+
+    fn pending(&self) -> Option<usize> {
+        Some(self.raw.len())
+    }
+}
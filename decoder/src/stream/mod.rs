@@ -1,10 +1,45 @@
+pub mod cobs;
+pub mod hdlc;
 mod raw;
 pub mod rzcobs;
 
+pub use cobs::Cobs;
+pub use hdlc::Hdlc;
 pub use raw::Raw;
 pub use rzcobs::Rzcobs;
 
-use crate::{DecodeError, Frame};
+use crate::{DecodeError, Frame, Table};
+
+/// Detects gaps in the sequence numbers of consecutive frames, and counts the frames dropped
+/// because of a CRC mismatch.
+#[derive(Default)]
+pub(crate) struct SequenceTracker {
+    next: Option<u8>,
+    corrupted: usize,
+}
+
+impl SequenceTracker {
+    /// Records `frame` and stores the number of frames lost and corrupted before it.
+    pub(crate) fn track(&mut self, frame: &mut Frame<'_>) {
+        let corrupted = std::mem::take(&mut self.corrupted);
+        frame.set_frames_corrupted(corrupted);
+
+        if let Some(n) = frame.sequence_number() {
+            let lost = self.next.map_or(0, |next| n.wrapping_sub(next));
+            // the corrupted frames used up sequence numbers; don't report them as lost too
+            frame.set_frames_lost((lost as usize).saturating_sub(corrupted));
+            self.next = Some(n.wrapping_add(1));
+        } else if let Some(dropped) = frame.frames_dropped() {
+            // the dropped frames used up sequence numbers; don't report them as lost too
+            self.next = self.next.map(|next| next.wrapping_add(dropped as u8));
+        }
+    }
+
+    /// Records a frame dropped because of a CRC mismatch.
+    pub(crate) fn corrupted(&mut self) {
+        self.corrupted += 1;
+    }
+}
 
 pub trait StreamDecoder {
     /// Push received data to the decoder. The decoder stores it
@@ -12,4 +47,138 @@ pub trait StreamDecoder {
     fn received(&mut self, data: &[u8]);
 
     fn decode(&mut self) -> Result<Frame<'_>, DecodeError>;
+
+    /// Returns how many of the received bytes haven't been decoded yet, if the decoder keeps track
+    /// of it.
+    ///
+    /// Separators in front of the next frame are not counted, so it starts this many bytes before
+    /// the end of the received data.
+    fn pending(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Stores `data` received on a stream whose frames end with `separator`.
+fn received_framed(raw: &mut Vec<u8>, mut data: &[u8], separator: u8) {
+    // Trim separators from the left, start storing at first other byte.
+    if raw.is_empty() {
+        while data.first() == Some(&separator) {
+            data = &data[1..]
+        }
+    }
+
+    raw.extend_from_slice(data);
+}
+
+/// Pops the frame ending at `end` off `raw`, along with the separators following it.
+fn advance_framed(raw: &mut Vec<u8>, end: usize, separator: u8) {
+    // Even if decoding the frame failed, pop the data off so we don't get stuck.
+    // Pop off the frame + 1 or more separator bytes
+    if let Some(next) = raw[end..].iter().position(|&x| x != separator) {
+        raw.drain(0..end + next);
+    } else {
+        raw.clear();
+    }
+}
+
+/// Decodes the next frame of a stream whose frames end with `separator`.
+///
+/// `unframe` turns the data of a frame, without the separator, back into a defmt frame. If
+/// `zero_padded`, like with rzCOBS, the unframed data may end with extra zeros.
+fn decode_framed<'t>(
+    table: &'t Table,
+    raw: &mut Vec<u8>,
+    sequence: &mut SequenceTracker,
+    separator: u8,
+    unframe: fn(&[u8]) -> Result<Vec<u8>, DecodeError>,
+    zero_padded: bool,
+) -> Result<Frame<'t>, DecodeError> {
+    loop {
+        // Find frame separator. If not found, we don't have enough data yet.
+        let end = raw
+            .iter()
+            .position(|&x| x == separator)
+            .ok_or(DecodeError::UnexpectedEof)?;
+
+        let frame = unframe(&raw[..end]);
+        advance_framed(raw, end, separator);
+
+        debug_assert!(raw.is_empty() || raw[0] != separator);
+
+        let mut data: Vec<u8> = match frame {
+            Ok(data) => data,
+            Err(_) if table.has_frame_crc() => {
+                sequence.corrupted();
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        if data.is_empty() {
+            // empty frames carry no log, loggers may send them to resynchronize the stream
+            continue;
+        }
+
+        // without padding, the CRC is in the last 2 bytes, and can be checked before decoding
+        if table.has_frame_crc() && !zero_padded {
+            match data.len().checked_sub(2) {
+                Some(len) if check_crc(&data, len) => data.truncate(len),
+                _ => {
+                    sequence.corrupted();
+                    continue;
+                }
+            }
+        }
+
+        return match table.decode(&data) {
+            Ok((_, consumed))
+                if table.has_frame_crc() && zero_padded && !check_crc(&data, consumed) =>
+            {
+                sequence.corrupted();
+                continue;
+            }
+            Ok((mut frame, _consumed)) => {
+                sequence.track(&mut frame);
+                Ok(frame)
+            }
+            // the position of the CRC is unknown, so the frame can't be told apart from a
+            // corrupted one
+            Err(_) if table.has_frame_crc() && zero_padded => {
+                sequence.corrupted();
+                continue;
+            }
+            Err(DecodeError::UnexpectedEof) => Err(DecodeError::Malformed),
+            Err(DecodeError::Malformed) => Err(DecodeError::Malformed),
+        };
+    }
+}
+
+/// Checks the CRC which follows the `len` bytes of the frame `data` (`frame-crc` feature of
+/// `defmt`).
+///
+/// With rzCOBS, which may pad the data with zeros, the CRC is checked after decoding, since its
+/// position is only known from the format string.
+fn check_crc(data: &[u8], len: usize) -> bool {
+    match data.get(len..len + 2) {
+        Some(&[a, b]) => {
+            crc16(&data[..len]) == u16::from_le_bytes([a, b])
+                && data[len + 2..].iter().all(|&x| x == 0)
+        }
+        _ => false,
+    }
+}
+
+/// Computes the CRC-16/X-25 of `data`, which is also the HDLC frame check sequence.
+pub(crate) fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xffff_u16;
+    for &byte in data {
+        crc ^= u16::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x8408
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
 }
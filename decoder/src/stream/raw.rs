@@ -1,9 +1,10 @@
-use super::StreamDecoder;
+use super::{SequenceTracker, StreamDecoder};
 use crate::{DecodeError, Frame, Table};
 
 pub struct Raw<'a> {
     table: &'a Table,
     data: Vec<u8>,
+    sequence: SequenceTracker,
 }
 
 impl<'a> Raw<'a> {
@@ -11,6 +12,7 @@ impl<'a> Raw<'a> {
         Self {
             table,
             data: Vec::new(),
+            sequence: SequenceTracker::default(),
         }
     }
 }
@@ -22,11 +24,16 @@ impl StreamDecoder for Raw<'_> {
 
     fn decode(&mut self) -> Result<Frame<'_>, DecodeError> {
         match self.table.decode(&self.data) {
-            Ok((frame, consumed)) => {
+            Ok((mut frame, consumed)) => {
                 self.data.drain(0..consumed);
+                self.sequence.track(&mut frame);
                 Ok(frame)
             }
             Err(e) => Err(e),
         }
     }
+
+    fn pending(&self) -> Option<usize> {
+        Some(self.data.len())
+    }
 }
@@ -1,4 +1,6 @@
-use super::StreamDecoder;
+use super::{
+    advance_framed, check_crc, decode_framed, received_framed, SequenceTracker, StreamDecoder,
+};
 use crate::{DecodeError, Frame, Table};
 use std::sync::Arc;
 
@@ -43,11 +45,13 @@ pub fn rzcobs_decode(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
 pub struct Rzcobs<'a> {
     table: &'a Table,
     raw: Vec<u8>,
+    sequence: SequenceTracker,
 }
 
 pub struct RzcobsOwned {
     table: Arc<Table>,
     raw: Vec<u8>,
+    sequence: SequenceTracker,
 }
 
 impl<'a> Rzcobs<'a> {
@@ -55,34 +59,29 @@ impl<'a> Rzcobs<'a> {
         Self {
             table,
             raw: Vec::new(),
+            sequence: SequenceTracker::default(),
         }
     }
 }
 
 impl StreamDecoder for Rzcobs<'_> {
     fn received(&mut self, data: &[u8]) {
-        received_inner(&mut self.raw, data);
+        received_framed(&mut self.raw, data, 0);
     }
 
     fn decode(&mut self) -> Result<Frame<'_>, DecodeError> {
-        // Find frame separator. If not found, we don't have enough data yet.
-        let zero = self
-            .raw
-            .iter()
-            .position(|&x| x == 0)
-            .ok_or(DecodeError::UnexpectedEof)?;
-
-        let frame = rzcobs_decode(&self.raw[..zero]);
-        advance_inner(&mut self.raw, zero);
-
-        debug_assert!(self.raw.is_empty() || self.raw[0] != 0);
+        decode_framed(
+            self.table,
+            &mut self.raw,
+            &mut self.sequence,
+            0,
+            rzcobs_decode,
+            true,
+        )
+    }
 
-        let frame: Vec<u8> = frame?;
-        match self.table.decode(&frame) {
-            Ok((frame, _consumed)) => Ok(frame),
-            Err(DecodeError::UnexpectedEof) => Err(DecodeError::Malformed),
-            Err(DecodeError::Malformed) => Err(DecodeError::Malformed),
-        }
+    fn pending(&self) -> Option<usize> {
+        Some(self.raw.len())
     }
 }
 
@@ -91,6 +90,7 @@ impl RzcobsOwned {
         Self {
             table,
             raw: Vec::new(),
+            sequence: SequenceTracker::default(),
         }
     }
 
@@ -101,7 +101,7 @@ impl RzcobsOwned {
 
 impl RzcobsOwned {
     pub fn received(&mut self, data: &[u8]) {
-        received_inner(&mut self.raw, data);
+        received_framed(&mut self.raw, data, 0);
     }
 
     pub fn frame_and_decode<F: FnMut(&[u8], Option<Frame<'_>>, usize)>(
@@ -116,38 +116,28 @@ impl RzcobsOwned {
         let frame = rzcobs_decode(&self.raw[..zero]);
         let decoded_len = frame.as_ref().map(|f| f.len()).unwrap_or(0);
 
-        match frame.map(|f| self.table.decode(&f)) {
-            Ok(Ok((frame, _consumed))) => {
+        match frame.map(|f| (self.table.decode(&f), f)) {
+            Ok((Ok((_, consumed)), data))
+                if self.table.has_frame_crc() && !check_crc(&data, consumed) =>
+            {
+                self.sequence.corrupted();
+                f(&self.raw[..zero], None, decoded_len);
+            }
+            Ok((Ok((mut frame, _consumed)), _)) => {
+                self.sequence.track(&mut frame);
                 f(&self.raw[..zero], Some(frame), decoded_len);
             }
-            Ok(Err(_e)) | Err(_e) => {
+            Ok((Err(_e), _)) | Err(_e) => {
+                // with a CRC, the frame can't be told apart from a corrupted one
+                if self.table.has_frame_crc() {
+                    self.sequence.corrupted();
+                }
                 f(&self.raw[..zero], None, decoded_len);
             }
         }
 
-        advance_inner(&mut self.raw, zero);
+        advance_framed(&mut self.raw, zero, 0);
         // debug_assert!(raw.is_empty() || raw[0] != 0);
         true
     }
 }
-
-fn received_inner(raw: &mut Vec<u8>, mut data: &[u8]) {
-    // Trim zeros from the left, start storing at first non-zero byte.
-    if raw.is_empty() {
-        while data.first() == Some(&0) {
-            data = &data[1..]
-        }
-    }
-
-    raw.extend_from_slice(data);
-}
-
-fn advance_inner(raw: &mut Vec<u8>, zero: usize) {
-    // Even if rzcobs_decode failed, pop the data off so we don't get stuck.
-    // Pop off the frame + 1 or more separator zero-bytes
-    if let Some(nonzero) = raw[zero..].iter().position(|&x| x != 0) {
-        raw.drain(0..zero + nonzero);
-    } else {
-        raw.clear();
-    }
-}
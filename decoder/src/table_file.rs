@@ -0,0 +1,241 @@
+//! Serialization of a [`Table`] and its [`Locations`] into a standalone file.
+//!
+//! This allows to archive the data needed to decode logs without the ELF file they came from.
+//! The file is JSON, with a `defmt_table` version field.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use anyhow::{anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    warn_ambiguous_enums, BitflagsKey, Encoding, EnumKey, Location, Locations, StringEntry, Table,
+    TableEntry, Tag,
+};
+
+const VERSION: u32 = 1;
+
+/// Names of the tags, as used in the symbols of the ELF file
+const TAGS: &[(Tag, &str)] = &[
+    (Tag::Prim, "defmt_prim"),
+    (Tag::Derived, "defmt_derived"),
+    (Tag::Bitflags, "defmt_bitflags"),
+    (Tag::Write, "defmt_write"),
+    (Tag::Timestamp, "defmt_timestamp"),
+    (Tag::BitflagsValue, "defmt_bitflags_value"),
+    (Tag::Str, "defmt_str"),
+    (Tag::Println, "defmt_println"),
+    (Tag::Trace, "defmt_trace"),
+    (Tag::Debug, "defmt_debug"),
+    (Tag::Info, "defmt_info"),
+    (Tag::Warn, "defmt_warn"),
+    (Tag::Error, "defmt_error"),
+];
+
+#[derive(Deserialize, Serialize)]
+struct TableFile {
+    defmt_table: u32,
+    encoding: String,
+    sequence_numbers: bool,
+    frame_crc: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fingerprint: Option<u64>,
+    timestamp: Option<Entry>,
+    entries: BTreeMap<usize, Entry>,
+    bitflags: Vec<Bitflags>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    enums: Vec<Enum>,
+    locations: BTreeMap<u64, FileLocation>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Entry {
+    tag: String,
+    string: String,
+    raw_symbol: String,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Bitflags {
+    ident: String,
+    package: String,
+    disambig: String,
+    crate_name: Option<String>,
+    values: Vec<(String, u128)>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Enum {
+    ident: String,
+    package: String,
+    disambig: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    crate_name: Option<String>,
+    values: Vec<(String, i128)>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct FileLocation {
+    file: PathBuf,
+    line: u64,
+    module: String,
+}
+
+impl Table {
+    /// Serializes the table, along with the `locations` of its log statements, into a standalone
+    /// file, which [`Table::deserialize`] loads again.
+    pub fn serialize(&self, locations: &Locations) -> Vec<u8> {
+        let file = TableFile {
+            defmt_table: VERSION,
+            encoding: encoding_name(self.encoding).to_string(),
+            sequence_numbers: self.sequence_numbers,
+            frame_crc: self.frame_crc,
+            fingerprint: self.fingerprint,
+            timestamp: self.timestamp.as_ref().map(Entry::from),
+            entries: self
+                .entries
+                .iter()
+                .map(|(index, entry)| (*index, Entry::from(entry)))
+                .collect(),
+            bitflags: self
+                .bitflags
+                .iter()
+                .map(|(key, values)| Bitflags {
+                    ident: key.ident.clone(),
+                    package: key.package.clone(),
+                    disambig: key.disambig.clone(),
+                    crate_name: key.crate_name.clone(),
+                    values: values.clone(),
+                })
+                .collect(),
+            enums: self
+                .enums
+                .iter()
+                .map(|(key, values)| Enum {
+                    ident: key.ident.clone(),
+                    package: key.package.clone(),
+                    disambig: key.disambig.clone(),
+                    crate_name: key.crate_name.clone(),
+                    values: values.clone(),
+                })
+                .collect(),
+            locations: locations
+                .iter()
+                .map(|(index, location)| {
+                    let location = FileLocation {
+                        file: location.file.clone(),
+                        line: location.line,
+                        module: location.module.clone(),
+                    };
+                    (*index, location)
+                })
+                .collect(),
+        };
+        serde_json::to_vec(&file).unwrap() // only fails for non-string map keys
+    }
+
+    /// Loads a table and the locations of its log statements from a file written by
+    /// [`Table::serialize`].
+    pub fn deserialize(bytes: &[u8]) -> Result<(Table, Locations), anyhow::Error> {
+        let file: TableFile = serde_json::from_slice(bytes)
+            .map_err(|e| anyhow!("failed to read defmt table: {}", e))?;
+        if file.defmt_table != VERSION {
+            bail!("unsupported defmt table version {}", file.defmt_table);
+        }
+
+        let table = Table {
+            timestamp: file.timestamp.map(TableEntry::try_from).transpose()?,
+            entries: file
+                .entries
+                .into_iter()
+                .map(|(index, entry)| Ok((index, TableEntry::try_from(entry)?)))
+                .collect::<Result<_, anyhow::Error>>()?,
+            bitflags: file
+                .bitflags
+                .into_iter()
+                .map(|bitflags| {
+                    let key = BitflagsKey {
+                        ident: bitflags.ident,
+                        package: bitflags.package,
+                        disambig: bitflags.disambig,
+                        crate_name: bitflags.crate_name,
+                    };
+                    (key, bitflags.values)
+                })
+                .collect(),
+            enums: file
+                .enums
+                .into_iter()
+                .map(|e| {
+                    let key = EnumKey {
+                        ident: e.ident,
+                        package: e.package,
+                        disambig: e.disambig,
+                        crate_name: e.crate_name,
+                    };
+                    (key, e.values)
+                })
+                .collect(),
+            encoding: file.encoding.parse()?,
+            sequence_numbers: file.sequence_numbers,
+            frame_crc: file.frame_crc,
+            fingerprint: file.fingerprint,
+        };
+        warn_ambiguous_enums(&table.enums);
+        let locations = file
+            .locations
+            .into_iter()
+            .map(|(index, location)| {
+                let location = Location {
+                    file: location.file,
+                    line: location.line,
+                    module: location.module,
+                };
+                (index, location)
+            })
+            .collect();
+
+        Ok((table, locations))
+    }
+}
+
+impl From<&TableEntry> for Entry {
+    fn from(entry: &TableEntry) -> Self {
+        let tag = TAGS
+            .iter()
+            .find(|(tag, _)| *tag == entry.string.tag)
+            .map(|(_, name)| name.to_string())
+            .unwrap(); // all tags are listed
+        Self {
+            tag,
+            string: entry.string.string.clone(),
+            raw_symbol: entry.raw_symbol.clone(),
+        }
+    }
+}
+
+impl TryFrom<Entry> for TableEntry {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: Entry) -> Result<Self, Self::Error> {
+        let tag = TAGS
+            .iter()
+            .find(|(_, name)| *name == entry.tag)
+            .map(|(tag, _)| tag.clone())
+            .ok_or_else(|| anyhow!("unknown tag `{}` in defmt table", entry.tag))?;
+        Ok(TableEntry::new(
+            StringEntry::new(tag, entry.string),
+            entry.raw_symbol,
+        ))
+    }
+}
+
+fn encoding_name(encoding: Encoding) -> &'static str {
+    match encoding {
+        Encoding::Raw => "raw",
+        Encoding::Rzcobs => "rzcobs",
+        Encoding::Varint => "varint",
+        Encoding::Cobs => "cobs",
+        Encoding::HdlcCrc16 => "hdlc-crc16",
+    }
+}
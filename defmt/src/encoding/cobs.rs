@@ -0,0 +1,111 @@
+/// Maximum number of non-zero bytes in a COBS block.
+const MAX_BLOCK: usize = 254;
+
+pub(crate) struct Encoder {
+    /// Non-zero bytes of the current block.
+    ///
+    /// A block is written once its length is known, since it is prefixed by it.
+    block: [u8; MAX_BLOCK],
+    len: u8,
+    /// Whether the last block written was a full one, which is not followed by an implied zero.
+    full: bool,
+    started: bool,
+}
+
+impl Encoder {
+    pub(crate) const fn new() -> Self {
+        Self {
+            block: [0; MAX_BLOCK],
+            len: 0,
+            full: false,
+            started: false,
+        }
+    }
+
+    pub(crate) fn start_frame(&mut self, mut write: impl FnMut(&[u8])) {
+        if !self.started {
+            self.started = true;
+
+            // Write a frame-separator at the very beginning. This allows the
+            // decoder to correctly decode the first frame if a previous boot had left a
+            // partly-written frame.
+            write(&[0x00]);
+        }
+    }
+
+    pub(crate) fn end_frame(&mut self, mut write: impl FnMut(&[u8])) {
+        // a frame ending with a full block needs no trailing empty block
+        if self.len != 0 || !self.full {
+            self.write_block(&mut write);
+        }
+        self.full = false;
+
+        // Write frame-separator.
+        write(&[0x00]);
+    }
+
+    pub(crate) fn write(&mut self, data: &[u8], mut write: impl FnMut(&[u8])) {
+        for &byte in data {
+            if byte == 0 {
+                // the zero is implied by the end of the block
+                self.write_block(&mut write);
+            } else {
+                self.block[usize::from(self.len)] = byte;
+                self.len += 1;
+                if usize::from(self.len) == MAX_BLOCK {
+                    // a full block is not followed by an implied zero
+                    self.write_block(&mut write);
+                }
+            }
+        }
+    }
+
+    fn write_block(&mut self, write: &mut impl FnMut(&[u8])) {
+        write(&[self.len + 1]);
+        write(&self.block[..usize::from(self.len)]);
+        self.full = usize::from(self.len) == MAX_BLOCK;
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        let long = (1..=254).collect::<Vec<u8>>();
+        let long_encoded = [&[0xff][..], &long, &[0x00]].concat();
+        let long_zero = [&long[..], &[0x00]].concat();
+        let long_zero_encoded = [&[0xff][..], &long, &[0x01, 0x01, 0x00]].concat();
+
+        let tests: &[(&[u8], &[u8])] = &[
+            (&[], &[0x01, 0x00]),
+            (&[0x00], &[0x01, 0x01, 0x00]),
+            (&[0x00, 0x00], &[0x01, 0x01, 0x01, 0x00]),
+            (
+                &[0x11, 0x22, 0x00, 0x33],
+                &[0x03, 0x11, 0x22, 0x02, 0x33, 0x00],
+            ),
+            (
+                &[0x11, 0x00, 0x00, 0x00],
+                &[0x02, 0x11, 0x01, 0x01, 0x01, 0x00],
+            ),
+            (&long, &long_encoded),
+            (&long_zero, &long_zero_encoded),
+        ];
+
+        for (dec, enc) in tests {
+            let mut res: Vec<u8> = Vec::new();
+
+            let mut e = Encoder::new();
+            e.started = true; // simulate that this is not the first frame.
+
+            e.start_frame(|data| res.extend(data));
+            e.write(dec, |data| res.extend(data));
+            e.end_frame(|data| res.extend(data));
+
+            assert_eq!(enc, &res);
+        }
+    }
+}
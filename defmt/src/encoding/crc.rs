@@ -0,0 +1,30 @@
+/// Initial value of the CRC-16/X-25, which is also the HDLC frame check sequence
+pub(crate) const CRC_INIT: u16 = 0xffff;
+
+/// Updates the CRC-16/X-25 `crc` with `data`.
+///
+/// The final CRC is the complement of the result. This is computed bit by bit to avoid a lookup
+/// table in flash.
+pub(crate) fn crc16(mut crc: u16, data: &[u8]) -> u16 {
+    for &byte in data {
+        crc ^= u16::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x8408
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check() {
+        assert_eq!(!crc16(CRC_INIT, b"123456789"), 0x906e);
+    }
+}
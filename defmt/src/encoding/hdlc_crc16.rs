@@ -0,0 +1,86 @@
+use super::crc::{crc16, CRC_INIT};
+
+/// Frame delimiter
+const FLAG: u8 = 0x7e;
+/// Escapes the next byte, which is transmitted XORed with `ESCAPE_XOR`
+const ESCAPE: u8 = 0x7d;
+const ESCAPE_XOR: u8 = 0x20;
+
+pub(crate) struct Encoder {
+    crc: u16,
+    started: bool,
+}
+
+impl Encoder {
+    pub(crate) const fn new() -> Self {
+        Self {
+            crc: CRC_INIT,
+            started: false,
+        }
+    }
+
+    pub(crate) fn start_frame(&mut self, mut write: impl FnMut(&[u8])) {
+        self.crc = CRC_INIT;
+
+        if !self.started {
+            self.started = true;
+
+            // Write a frame-separator at the very beginning. This allows the
+            // decoder to correctly decode the first frame if a previous boot had left a
+            // partly-written frame.
+            write(&[FLAG]);
+        }
+    }
+
+    pub(crate) fn end_frame(&mut self, mut write: impl FnMut(&[u8])) {
+        // the frame check sequence is sent least significant byte first
+        let fcs = !self.crc;
+        write_escaped(&fcs.to_le_bytes(), &mut write);
+
+        // Write frame-separator.
+        write(&[FLAG]);
+    }
+
+    pub(crate) fn write(&mut self, data: &[u8], mut write: impl FnMut(&[u8])) {
+        self.crc = crc16(self.crc, data);
+        write_escaped(data, &mut write);
+    }
+}
+
+fn write_escaped(data: &[u8], write: &mut impl FnMut(&[u8])) {
+    let mut data = data;
+    while let Some(i) = data.iter().position(|&b| b == FLAG || b == ESCAPE) {
+        write(&data[..i]);
+        write(&[ESCAPE, data[i] ^ ESCAPE_XOR]);
+        data = &data[i + 1..];
+    }
+    write(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        let tests: &[(&[u8], &[u8])] = &[
+            (&[], &[0x00, 0x00, 0x7e]),
+            (b"123456789", b"123456789\x6e\x90\x7e"),
+            (&[0x7e], &[0x7d, 0x5e, 0x81, 0x6a, 0x7e]),
+            (&[0x7d, 0x00], &[0x7d, 0x5d, 0x00, 0xfb, 0x4f, 0x7e]),
+        ];
+
+        for (dec, enc) in tests {
+            let mut res: Vec<u8> = Vec::new();
+
+            let mut e = Encoder::new();
+            e.started = true; // simulate that this is not the first frame.
+
+            e.start_frame(|data| res.extend(data));
+            e.write(dec, |data| res.extend(data));
+            e.end_frame(|data| res.extend(data));
+
+            assert_eq!(enc, &res);
+        }
+    }
+}
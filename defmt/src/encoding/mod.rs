@@ -1,10 +1,61 @@
 #[cfg(all(feature = "encoding-raw", feature = "encoding-rzcobs"))]
 compile_error!("Multiple `encoding-*` features are enabled. You may only enable one.");
 
+#[cfg(all(feature = "encoding-raw", feature = "encoding-varint"))]
+compile_error!("Multiple `encoding-*` features are enabled. You may only enable one.");
+
+#[cfg(all(feature = "encoding-raw", feature = "encoding-cobs"))]
+compile_error!("Multiple `encoding-*` features are enabled. You may only enable one.");
+
+#[cfg(all(feature = "encoding-raw", feature = "encoding-hdlc-crc16"))]
+compile_error!("Multiple `encoding-*` features are enabled. You may only enable one.");
+
+#[cfg(all(feature = "encoding-rzcobs", feature = "encoding-varint"))]
+compile_error!("Multiple `encoding-*` features are enabled. You may only enable one.");
+
+#[cfg(all(feature = "encoding-rzcobs", feature = "encoding-cobs"))]
+compile_error!("Multiple `encoding-*` features are enabled. You may only enable one.");
+
+#[cfg(all(feature = "encoding-rzcobs", feature = "encoding-hdlc-crc16"))]
+compile_error!("Multiple `encoding-*` features are enabled. You may only enable one.");
+
+#[cfg(all(feature = "encoding-varint", feature = "encoding-cobs"))]
+compile_error!("Multiple `encoding-*` features are enabled. You may only enable one.");
+
+#[cfg(all(feature = "encoding-varint", feature = "encoding-hdlc-crc16"))]
+compile_error!("Multiple `encoding-*` features are enabled. You may only enable one.");
+
+#[cfg(all(feature = "encoding-cobs", feature = "encoding-hdlc-crc16"))]
+compile_error!("Multiple `encoding-*` features are enabled. You may only enable one.");
+
+#[cfg(all(
+    feature = "frame-crc",
+    any(
+        feature = "encoding-raw",
+        feature = "encoding-cobs",
+        feature = "encoding-hdlc-crc16"
+    )
+))]
+compile_error!("The `frame-crc` feature is only supported by the rzCOBS based encodings.");
+
+#[cfg(any(feature = "encoding-hdlc-crc16", feature = "frame-crc"))]
+mod crc;
+
 #[cfg_attr(feature = "encoding-raw", path = "raw.rs")]
-#[cfg_attr(not(feature = "encoding-raw"), path = "rzcobs.rs")]
+#[cfg_attr(feature = "encoding-cobs", path = "cobs.rs")]
+#[cfg_attr(feature = "encoding-hdlc-crc16", path = "hdlc_crc16.rs")]
+#[cfg_attr(
+    not(any(
+        feature = "encoding-raw",
+        feature = "encoding-cobs",
+        feature = "encoding-hdlc-crc16"
+    )),
+    path = "rzcobs.rs"
+)]
 mod inner;
 
+// NOTE `encoding-varint` only changes how integers are written; frames are framed with rzCOBS
+
 // This wrapper struct is to avoid copypasting the public docs in all the impls.
 
 /// Encode raw defmt frames for sending over the wire.
@@ -9,10 +9,18 @@
 //   1nnnnnnn => output n+7 bytes from stream, output 0x00
 //   11111111 => output 134 bytes from stream
 
+#[cfg(feature = "frame-crc")]
+use super::crc::{crc16, CRC_INIT};
+
 pub(crate) struct Encoder {
     run: u8,
     zeros: u8,
     started: bool,
+    /// CRC of the current frame, sent at its end (`frame-crc` feature)
+    ///
+    /// Is `None` until data is written, empty frames are sent without CRC.
+    #[cfg(feature = "frame-crc")]
+    crc: Option<u16>,
 }
 
 impl Encoder {
@@ -21,6 +29,8 @@ impl Encoder {
             run: 0,
             zeros: 0,
             started: false,
+            #[cfg(feature = "frame-crc")]
+            crc: None,
         }
     }
 
@@ -37,6 +47,12 @@ impl Encoder {
     }
 
     pub fn end_frame(&mut self, mut write: impl FnMut(&[u8])) {
+        #[cfg(feature = "frame-crc")]
+        if let Some(crc) = self.crc.take() {
+            // the CRC is sent least significant byte first, as part of the frame data
+            self.encode(&(!crc).to_le_bytes(), &mut write);
+        }
+
         let mut write_byte = move |b: u8| write(&[b]);
 
         // Finish writing the previous symbol if needed.
@@ -53,7 +69,16 @@ impl Encoder {
         self.zeros = 0;
     }
 
-    pub fn write(&mut self, data: &[u8], mut write: impl FnMut(&[u8])) {
+    pub fn write(&mut self, data: &[u8], write: impl FnMut(&[u8])) {
+        #[cfg(feature = "frame-crc")]
+        {
+            self.crc = Some(crc16(self.crc.unwrap_or(CRC_INIT), data));
+        }
+
+        self.encode(data, write)
+    }
+
+    fn encode(&mut self, data: &[u8], mut write: impl FnMut(&[u8])) {
         let mut write_byte = move |b: u8| write(&[b]);
 
         for &byte in data {
@@ -88,7 +113,8 @@ impl Encoder {
 }
 
 #[cfg(feature = "unstable-test")]
-#[cfg(test)]
+// NOTE the expected encodings don't include a CRC
+#[cfg(all(test, not(feature = "frame-crc")))]
 mod tests {
     use super::*;
 
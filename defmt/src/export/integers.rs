@@ -9,14 +9,62 @@ macro_rules! write_to_le_bytes {
     };
 }
 
+#[cfg(not(feature = "encoding-varint"))]
 write_to_le_bytes!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
 
+#[cfg(feature = "encoding-varint")]
+write_to_le_bytes!(u8, i8);
+
+/// Writes unsigned integers as LEB128 varints: 7 bits per byte, least significant group first,
+/// with the most significant bit set on all bytes but the last.
+#[cfg(feature = "encoding-varint")]
+macro_rules! write_leb128 {
+    ($($s:ident),*) => {
+        $(/// Implementation detail
+        pub fn $s(b: &$s) {
+            let mut n = *b;
+            let mut buf = [0; ($s::BITS as usize + 6) / 7];
+            let mut len = 0;
+            loop {
+                let byte = (n & 0x7f) as u8;
+                n >>= 7;
+                if n == 0 {
+                    buf[len] = byte;
+                    len += 1;
+                    break;
+                }
+                buf[len] = byte | 0x80;
+                len += 1;
+            }
+            write(&buf[..len])
+        })*
+    };
+}
+
+/// Writes signed integers as zigzag-encoded LEB128 varints, so that small negative values are
+/// short too.
+#[cfg(feature = "encoding-varint")]
+macro_rules! write_zigzag_leb128 {
+    ($($s:ident => $u:ident),*) => {
+        $(/// Implementation detail
+        pub fn $s(b: &$s) {
+            $u(&(((*b << 1) ^ (*b >> ($s::BITS - 1))) as $u))
+        })*
+    };
+}
+
+#[cfg(feature = "encoding-varint")]
+write_leb128!(u16, u32, u64, u128);
+
+#[cfg(feature = "encoding-varint")]
+write_zigzag_leb128!(i16 => u16, i32 => u32, i64 => u64, i128 => u128);
+
 /// Implementation detail
 pub fn usize(b: &usize) {
-    write(&(*b as u32).to_le_bytes())
+    u32(&(*b as u32))
 }
 
 /// Implementation detail
 pub fn isize(b: &isize) {
-    write(&(*b as i32).to_le_bytes())
+    i32(&(*b as i32))
 }
@@ -85,6 +85,29 @@ pub fn write(bytes: &[u8]) {
     unsafe { _defmt_write(bytes) }
 }
 
+/// Only to be used by the defmt macros
+#[cfg(feature = "unstable-test")]
+pub fn enabled(level: crate::Level, _module_path: &str) -> bool {
+    crate::filter::level_enabled(level)
+}
+
+/// Only to be used by the defmt macros
+#[cfg(all(not(feature = "unstable-test"), feature = "runtime-filter"))]
+#[inline(always)]
+pub fn enabled(level: crate::Level, module_path: &str) -> bool {
+    extern "Rust" {
+        fn _defmt_filter(level: crate::Level, module_path: &str) -> bool;
+    }
+    crate::filter::level_enabled(level) && unsafe { _defmt_filter(level, module_path) }
+}
+
+/// Only to be used by the defmt macros
+#[cfg(all(not(feature = "unstable-test"), not(feature = "runtime-filter")))]
+#[inline(always)]
+pub fn enabled(level: crate::Level, _module_path: &str) -> bool {
+    crate::filter::level_enabled(level)
+}
+
 /// For testing purposes
 #[cfg(feature = "unstable-test")]
 pub fn timestamp(_fmt: crate::Formatter<'_>) {}
@@ -98,6 +121,24 @@ pub fn timestamp(fmt: crate::Formatter<'_>) {
     unsafe { _defmt_timestamp(fmt) }
 }
 
+/// For testing purposes
+#[cfg(feature = "unstable-test")]
+pub fn fingerprint() -> u64 {
+    0
+}
+
+/// Returns the fingerprint defined by `defmt::fingerprint!`, or `0` if there is none.
+///
+/// Only to be used by loggers, which send it to the host in a handshake frame.
+#[cfg(not(feature = "unstable-test"))]
+#[inline(always)]
+pub fn fingerprint() -> u64 {
+    extern "Rust" {
+        static _defmt_fingerprint: u64;
+    }
+    unsafe { _defmt_fingerprint }
+}
+
 /// Returns the interned string at `address`.
 pub fn make_istr(address: u16) -> Str {
     Str { address }
@@ -188,7 +229,59 @@ pub fn fmt_array<T: Format>(a: &[T]) {
 
 /// Implementation detail
 pub fn istr(s: &Str) {
-    write(&s.address.to_le_bytes())
+    u16(&s.address)
+}
+
+/// Writes the string `index` into `buf` the way `istr` writes it on the wire.
+///
+/// Only to be used by loggers which send frames of their own, like dropped frames reports.
+#[cfg(not(feature = "encoding-varint"))]
+pub fn encode_index(index: u16, buf: &mut [u8; 3]) -> &[u8] {
+    buf[..2].copy_from_slice(&index.to_le_bytes());
+    &buf[..2]
+}
+
+/// Writes the string `index` into `buf` the way `istr` writes it on the wire.
+///
+/// Only to be used by loggers which send frames of their own, like dropped frames reports.
+#[cfg(feature = "encoding-varint")]
+pub fn encode_index(mut index: u16, buf: &mut [u8; 3]) -> &[u8] {
+    let mut len = 0;
+    while index >= 0x80 {
+        buf[len] = index as u8 | 0x80;
+        index >>= 7;
+        len += 1;
+    }
+    buf[len] = index as u8;
+    &buf[..=len]
+}
+
+/// Reads the string index at the start of the log frame `bytes`.
+///
+/// Returns `None` if `bytes` is too short. Only to be used by loggers which route frames by
+/// level, see `IdRanges`.
+#[cfg(not(feature = "encoding-varint"))]
+pub fn decode_index(bytes: &[u8]) -> Option<u16> {
+    match *bytes {
+        [a, b, ..] => Some(u16::from_le_bytes([a, b])),
+        _ => None,
+    }
+}
+
+/// Reads the string index at the start of the log frame `bytes`.
+///
+/// Returns `None` if `bytes` is too short. Only to be used by loggers which route frames by
+/// level, see `IdRanges`.
+#[cfg(feature = "encoding-varint")]
+pub fn decode_index(bytes: &[u8]) -> Option<u16> {
+    let mut index = 0;
+    for (i, byte) in bytes.iter().take(3).enumerate() {
+        index |= u16::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some(index);
+        }
+    }
+    None
 }
 
 /// Implementation detail
@@ -208,10 +301,32 @@ pub fn display(val: &dyn core::fmt::Display) {
     write(&[0xff]);
 }
 
+/// Implementation detail
+///
+/// Must only be called while the logger is acquired, right after the format string index.
+#[cfg(all(feature = "sequence-numbers", not(feature = "unstable-test")))]
+fn sequence_number() {
+    extern "Rust" {
+        fn _defmt_sequence_number() -> u8;
+    }
+    u8(&unsafe { _defmt_sequence_number() });
+}
+
+/// For testing purposes
+#[cfg(all(feature = "sequence-numbers", feature = "unstable-test"))]
+fn sequence_number() {
+    u8(&crate::default_sequence_number());
+}
+
+#[cfg(not(feature = "sequence-numbers"))]
+#[inline(always)]
+fn sequence_number() {}
+
 #[inline(never)]
 pub unsafe fn acquire_and_header(s: &Str) {
     acquire();
     istr(s);
+    sequence_number();
     timestamp(make_formatter());
 }
 
@@ -220,6 +335,7 @@ pub fn acquire_header_and_release(s: &Str) {
     // safety: will be released a few lines further down
     unsafe { acquire() };
     istr(s);
+    sequence_number();
     timestamp(make_formatter());
     // safety: acquire() was called a few lines above
     unsafe { release() };
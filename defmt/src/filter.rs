@@ -0,0 +1,67 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// The severity of a log statement, as selected by the logging macro.
+///
+/// Levels are ordered from the most verbose (`Trace`) to the most severe (`Error`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Emitted by [`trace!`](crate::trace)
+    Trace,
+    /// Emitted by [`debug!`](crate::debug)
+    Debug,
+    /// Emitted by [`info!`](crate::info)
+    Info,
+    /// Emitted by [`warn!`](crate::warn)
+    Warn,
+    /// Emitted by [`error!`](crate::error), and by the panicking macros
+    Error,
+}
+
+#[cfg(not(feature = "unstable-test"))]
+static MIN_LEVEL: AtomicU8 = AtomicU8::new(Level::Trace as u8);
+
+// thread local when testing, so unit tests can run in parallel
+#[cfg(feature = "unstable-test")]
+thread_local! {
+    static MIN_LEVEL: AtomicU8 = const { AtomicU8::new(Level::Trace as u8) };
+}
+
+/// Sets the minimum level which is logged at runtime.
+///
+/// Log statements of a less severe level are skipped before the global logger is acquired. For
+/// example, `set_min_level(Level::Info)` skips the `trace!` and `debug!` statements.
+///
+/// This filter only applies to the log statements which are enabled at compile time by
+/// `DEFMT_LOG`; it can't enable a statement which was left out of the program. Defaults to
+/// [`Level::Trace`], i.e. everything which is compiled in is logged.
+pub fn set_min_level(level: Level) {
+    #[cfg(not(feature = "unstable-test"))]
+    MIN_LEVEL.store(level as u8, Ordering::Relaxed);
+    #[cfg(feature = "unstable-test")]
+    MIN_LEVEL.with(|l| l.store(level as u8, Ordering::Relaxed));
+}
+
+/// Returns the level set by [`set_min_level`].
+pub fn min_level() -> Level {
+    match load_min_level() {
+        0 => Level::Trace,
+        1 => Level::Debug,
+        2 => Level::Info,
+        3 => Level::Warn,
+        _ => Level::Error,
+    }
+}
+
+/// Is `level` enabled by [`set_min_level`]?
+#[inline(always)]
+pub(crate) fn level_enabled(level: Level) -> bool {
+    level as u8 >= load_min_level()
+}
+
+#[inline(always)]
+fn load_min_level() -> u8 {
+    #[cfg(not(feature = "unstable-test"))]
+    return MIN_LEVEL.load(Ordering::Relaxed);
+    #[cfg(feature = "unstable-test")]
+    return MIN_LEVEL.with(|l| l.load(Ordering::Relaxed));
+}
@@ -41,17 +41,45 @@ static DEFMT_VERSION: u8 = 0;
 #[cfg_attr(target_os = "macos", link_section = ".defmt,end.ENCODING")]
 #[cfg_attr(not(target_os = "macos"), link_section = ".defmt.end")]
 #[cfg_attr(feature = "encoding-raw", export_name = "_defmt_encoding_ = raw")]
+#[cfg_attr(feature = "encoding-varint", export_name = "_defmt_encoding_ = varint")]
+#[cfg_attr(feature = "encoding-cobs", export_name = "_defmt_encoding_ = cobs")]
 #[cfg_attr(
-    not(feature = "encoding-raw"),
+    feature = "encoding-hdlc-crc16",
+    export_name = "_defmt_encoding_ = hdlc-crc16"
+)]
+#[cfg_attr(
+    not(any(
+        feature = "encoding-raw",
+        feature = "encoding-varint",
+        feature = "encoding-cobs",
+        feature = "encoding-hdlc-crc16"
+    )),
     export_name = "_defmt_encoding_ = rzcobs"
 )]
 #[allow(missing_docs)]
 #[doc(hidden)]
 pub static DEFMT_ENCODING: u8 = 0;
 
+// Tells the decoder that frame headers contain a sequence number
+#[cfg(feature = "sequence-numbers")]
+#[used]
+#[cfg_attr(target_os = "macos", link_section = ".defmt,end.SEQUENCE_NUMBERS")]
+#[cfg_attr(not(target_os = "macos"), link_section = ".defmt.end")]
+#[export_name = "_defmt_sequence_numbers_"]
+static DEFMT_SEQUENCE_NUMBERS: u8 = 0;
+
+// Tells the decoder that frames end with a CRC
+#[cfg(feature = "frame-crc")]
+#[used]
+#[cfg_attr(target_os = "macos", link_section = ".defmt,end.FRAME_CRC")]
+#[cfg_attr(not(target_os = "macos"), link_section = ".defmt.end")]
+#[export_name = "_defmt_frame_crc_"]
+static DEFMT_FRAME_CRC: u8 = 0;
+
 mod encoding;
 #[doc(hidden)]
 pub mod export;
+mod filter;
 mod formatter;
 mod impls;
 #[cfg(all(test, feature = "unstable-test"))]
@@ -60,6 +88,7 @@ mod traits;
 
 pub use crate::{
     encoding::Encoder,
+    filter::{min_level, set_min_level, Level},
     formatter::{Formatter, Str},
     impls::adapter::{Debug2Format, Display2Format},
     traits::{Format, Logger},
@@ -340,6 +369,29 @@ pub use defmt_macros::global_logger;
 /// ```
 pub use defmt_macros::timestamp;
 
+/// Gives the firmware a fingerprint, which lets the host check that it decodes the logs with the
+/// right ELF file.
+///
+/// The fingerprint is a random value, which changes whenever the crate invoking the macro is
+/// rebuilt; Cargo does that whenever the crate or one of its dependencies changed. It is stored
+/// in the ELF file, and global loggers which support it (like `defmt-rtt`) send it to the host in
+/// a handshake frame. The decoder then warns about logs decoded with a different ELF file, whose
+/// format strings may not match the ones of the firmware.
+///
+/// The fingerprint identifies a build, not the contents of the `.defmt` table: rebuilding the
+/// same sources gives a different fingerprint, so the decoder also warns if the firmware was
+/// rebuilt but not flashed again. For the same reason, builds of a firmware which invokes
+/// `fingerprint!` are not reproducible.
+///
+/// `fingerprint!` must only be used once across the crate graph, preferably in the binary crate.
+///
+/// # Examples
+///
+/// ```
+/// defmt::fingerprint!();
+/// ```
+pub use defmt_macros::fingerprint;
+
 /// Generates a bitflags structure that can be formatted with defmt.
 ///
 /// This macro is a wrapper around the [`bitflags!`] crate, and provides an (almost) identical
@@ -380,11 +432,37 @@ pub use defmt_macros::Format;
 #[export_name = "__defmt_default_timestamp"]
 fn default_timestamp(_f: Formatter<'_>) {}
 
+// Without `defmt::fingerprint!`, loggers don't send a fingerprint.
+#[export_name = "__defmt_default_fingerprint"]
+static DEFAULT_FINGERPRINT: u64 = 0;
+
 #[export_name = "__defmt_default_panic"]
 fn default_panic() -> ! {
     core::panic!()
 }
 
+// With the `runtime-filter` feature, a global logger may provide `_defmt_filter` to drop frames at
+// runtime. Without it, every frame is logged.
+#[export_name = "__defmt_default_filter"]
+fn default_filter(_level: Level, _module_path: &str) -> bool {
+    true
+}
+
+// A global logger which sends the frames on several streams, like `defmt-rtt` with extra up
+// channels, may provide `_defmt_sequence_number` to number the frames of each stream separately.
+// By default, a single counter is shared by all frames. Only called while the logger is acquired,
+// which serializes the updates of the counter.
+#[export_name = "__defmt_default_sequence_number"]
+fn default_sequence_number() -> u8 {
+    use core::sync::atomic::{AtomicU8, Ordering};
+
+    static SEQUENCE_NUMBER: AtomicU8 = AtomicU8::new(0);
+
+    let n = SEQUENCE_NUMBER.load(Ordering::Relaxed);
+    SEQUENCE_NUMBER.store(n.wrapping_add(1), Ordering::Relaxed);
+    n
+}
+
 /// Block until host has read all pending data.
 ///
 /// The flush operation will not fail, but might not succeed in flushing _all_ pending data. It is
@@ -10,6 +10,21 @@ fn log_levels() {
     defmt::error!("test error");
 }
 
+#[test]
+fn min_level() {
+    assert_eq!(defmt::min_level(), defmt::Level::Trace);
+    assert!(defmt::export::enabled(defmt::Level::Trace, module_path!()));
+
+    defmt::set_min_level(defmt::Level::Info);
+    assert_eq!(defmt::min_level(), defmt::Level::Info);
+    assert!(!defmt::export::enabled(defmt::Level::Trace, module_path!()));
+    assert!(!defmt::export::enabled(defmt::Level::Debug, module_path!()));
+    assert!(defmt::export::enabled(defmt::Level::Info, module_path!()));
+    assert!(defmt::export::enabled(defmt::Level::Error, module_path!()));
+
+    defmt::set_min_level(defmt::Level::Trace);
+}
+
 #[test]
 fn str() {
     defmt::info!("Hello, {=str}", "world");
@@ -32,3 +47,33 @@ fn trailing_comma() {
     defmt::warn!("test warn {=?}", 0,);
     defmt::error!("test error {=?}", 0,);
 }
+
+#[cfg(feature = "encoding-varint")]
+#[test]
+fn leb128() {
+    use defmt::export::{fetch_bytes, i16, i64, u128, u16, u32};
+
+    fetch_bytes();
+    u32(&0);
+    assert_eq!(fetch_bytes(), [0]);
+    u16(&127);
+    assert_eq!(fetch_bytes(), [0x7f]);
+    u32(&128);
+    assert_eq!(fetch_bytes(), [0x80, 0x01]);
+    u128(&u128::MAX);
+    let mut max = [0xff; 19];
+    max[18] = 0x03;
+    assert_eq!(fetch_bytes(), max);
+
+    // zigzag
+    i16(&0);
+    assert_eq!(fetch_bytes(), [0]);
+    i16(&-1);
+    assert_eq!(fetch_bytes(), [1]);
+    i16(&1);
+    assert_eq!(fetch_bytes(), [2]);
+    i64(&i64::MIN);
+    let mut min = [0xff; 10];
+    min[9] = 0x01;
+    assert_eq!(fetch_bytes(), min);
+}
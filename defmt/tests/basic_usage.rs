@@ -1,5 +1,6 @@
 fn main() {
     defmt::info!("hello");
+    defmt::info!("conn open {=u8}", 3, peer = 1u8, rssi = -40i8);
 }
 
 #[defmt::global_logger]
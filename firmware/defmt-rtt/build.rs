@@ -1,7 +1,11 @@
-use std::{env, path::PathBuf};
+use std::{env, fmt::Write as _, path::PathBuf};
+
+/// Log levels in the order used by `LEVEL_ROUTES`.
+const LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
 
 fn main() {
     println!("cargo:rerun-if-env-changed=DEFMT_RTT_BUFFER_SIZE");
+    println!("cargo:rerun-if-env-changed=DEFMT_RTT_EXTRA_CHANNELS");
 
     let size = env::var("DEFMT_RTT_BUFFER_SIZE")
         .map(|s| {
@@ -10,19 +14,115 @@ fn main() {
         })
         .unwrap_or(1024_usize);
 
+    let extra_channels = env::var("DEFMT_RTT_EXTRA_CHANNELS")
+        .map(|s| parse_extra_channels(&s))
+        .unwrap_or_default();
+
+    // by default every level is routed to the "defmt" channel
+    let mut level_routes = [0; LEVELS.len()];
+    for (i, channel) in extra_channels.iter().enumerate() {
+        for level in &channel.levels {
+            let idx = LEVELS.iter().position(|l| l == level).unwrap();
+            if level_routes[idx] != 0 {
+                panic!(
+                    "DEFMT_RTT_EXTRA_CHANNELS: level `{level}` is routed to more than one channel"
+                );
+            }
+            level_routes[idx] = i + 1;
+        }
+    }
+
     let out_dir_path = PathBuf::from(env::var_os("OUT_DIR").unwrap());
-    let out_file_path = out_dir_path.join("consts.rs");
 
     std::fs::write(
-        out_file_path,
+        out_dir_path.join("consts.rs"),
         format!(
             "/// RTT buffer size (default: 1024).
             ///
             /// Can be customized by setting the `DEFMT_RTT_BUFFER_SIZE` environment variable.
             /// Use a power of 2 for best performance.
-            pub(crate) const BUF_SIZE: usize = {};",
-            size
+            pub(crate) const BUF_SIZE: usize = {};
+
+            /// Number of up channels: the \"defmt\" channel plus the ones configured through the
+            /// `DEFMT_RTT_EXTRA_CHANNELS` environment variable.
+            pub(crate) const UP_CHANNELS: usize = {};
+
+            /// Up channel for each log level, from `trace` to `error`.
+            pub(crate) const LEVEL_ROUTES: [usize; 5] = {:?};",
+            size,
+            extra_channels.len() + 1,
+            level_routes,
         ),
     )
     .unwrap();
+
+    // the buffers and names of the extra channels, and the expression used to initialize the
+    // up channels of the RTT header
+    let mut statics = String::new();
+    let mut up_channels = String::from("[Channel::new(NAME.as_ptr(), BUFFER.get(), BUF_SIZE),");
+    for (i, channel) in extra_channels.iter().enumerate() {
+        let idx = i + 1;
+        let name_len = channel.name.len() + 1;
+        let ExtraChannel { name, size, .. } = channel;
+        write!(
+            statics,
+            "#[cfg_attr(target_os = \"macos\", link_section = \".uninit,defmt-rtt.BUFFER{idx}\")]
+            #[cfg_attr(not(target_os = \"macos\"), link_section = \".uninit.defmt-rtt.BUFFER{idx}\")]
+            static BUFFER{idx}: Buffer<{size}> = Buffer::new();
+
+            #[cfg_attr(target_os = \"macos\", link_section = \".data,defmt-rtt.NAME{idx}\")]
+            #[cfg_attr(not(target_os = \"macos\"), link_section = \".data.defmt-rtt.NAME{idx}\")]
+            static NAME{idx}: [u8; {name_len}] = *b\"{name}\\0\";
+            "
+        )
+        .unwrap();
+        write!(
+            up_channels,
+            "Channel::new(NAME{idx}.as_ptr(), BUFFER{idx}.get(), {size}),"
+        )
+        .unwrap();
+    }
+    up_channels.push(']');
+
+    std::fs::write(out_dir_path.join("extra_channels.rs"), statics).unwrap();
+    std::fs::write(out_dir_path.join("up_channels.rs"), up_channels).unwrap();
+}
+
+struct ExtraChannel {
+    name: String,
+    size: usize,
+    levels: Vec<String>,
+}
+
+/// Parses a comma-separated list of `name:size[:level+level...]` channel descriptions.
+fn parse_extra_channels(input: &str) -> Vec<ExtraChannel> {
+    let mut channels = vec![];
+    for description in input.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let mut parts = description.split(':');
+        let name = parts.next().unwrap().to_string();
+        let is_valid_char = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+        if name.is_empty() || !name.chars().all(is_valid_char) {
+            panic!("DEFMT_RTT_EXTRA_CHANNELS: invalid channel name `{name}`");
+        }
+
+        let size = match parts.next().map(str::parse) {
+            Some(Ok(size)) if size > 0 => size,
+            _ => panic!("DEFMT_RTT_EXTRA_CHANNELS: invalid size for channel `{name}`"),
+        };
+
+        let mut levels = vec![];
+        for level in parts.next().into_iter().flat_map(|s| s.split('+')) {
+            if !LEVELS.contains(&level) {
+                panic!("DEFMT_RTT_EXTRA_CHANNELS: unknown log level `{level}`");
+            }
+            levels.push(level.to_string());
+        }
+
+        if parts.next().is_some() {
+            panic!("DEFMT_RTT_EXTRA_CHANNELS: malformed channel description `{description}`");
+        }
+
+        channels.push(ExtraChannel { name, size, levels });
+    }
+    channels
 }
@@ -3,18 +3,20 @@ use core::{
     sync::atomic::{AtomicUsize, Ordering},
 };
 
-use crate::{consts::BUF_SIZE, MODE_BLOCK_IF_FULL, MODE_MASK};
+use crate::{MODE_BLOCK_IF_FULL, MODE_MASK, MODE_NON_BLOCKING_TRIM};
 
-/// RTT Up channel
+/// RTT channel
+///
+/// Up and down channels share the same layout, only the direction of the data differs.
 #[repr(C)]
 pub(crate) struct Channel {
     pub name: *const u8,
     /// Pointer to the RTT buffer.
     pub buffer: *mut u8,
     pub size: usize,
-    /// Written by the target.
+    /// Written by the target for up channels, by the host for down channels.
     pub write: AtomicUsize,
-    /// Written by the host.
+    /// Written by the host for up channels, by the target for down channels.
     pub read: AtomicUsize,
     /// Channel properties.
     ///
@@ -23,20 +25,49 @@ pub(crate) struct Channel {
 }
 
 impl Channel {
-    pub fn write_all(&self, mut bytes: &[u8]) {
+    pub const fn new(name: *const u8, buffer: *mut u8, size: usize) -> Channel {
+        Channel {
+            name,
+            buffer,
+            size,
+            write: AtomicUsize::new(0),
+            read: AtomicUsize::new(0),
+            flags: AtomicUsize::new(MODE_NON_BLOCKING_TRIM),
+        }
+    }
+
+    /// Write `bytes` to an up channel.
+    ///
+    /// In non-blocking mode, the bytes which don't fit in the buffer are dropped. Returns `false`
+    /// if that happened.
+    pub fn write_all(&self, mut bytes: &[u8]) -> bool {
         // the host-connection-status is only modified after RAM initialization while the device is
         // halted, so we only need to check it once before the write-loop
-        let write = match self.host_is_connected() {
-            _ if cfg!(feature = "disable-blocking-mode") => Self::nonblocking_write,
-            true => Self::blocking_write,
-            false => Self::nonblocking_write,
-        };
+        let blocking = !cfg!(feature = "disable-blocking-mode") && self.host_is_connected();
 
         while !bytes.is_empty() {
-            let consumed = write(self, bytes);
-            if consumed != 0 {
-                bytes = &bytes[consumed..];
-            }
+            let consumed = if blocking {
+                self.blocking_write(bytes)
+            } else {
+                match self.nonblocking_write(bytes) {
+                    // the buffer is full
+                    0 => return false,
+                    consumed => consumed,
+                }
+            };
+            bytes = &bytes[consumed..];
+        }
+        true
+    }
+
+    /// How many bytes can be written to an up channel before it is full?
+    pub fn free_space(&self) -> usize {
+        let read = self.read.load(Ordering::Relaxed);
+        let write = self.write.load(Ordering::Acquire);
+        if read > write {
+            read - write - 1
+        } else {
+            self.size - write + read - 1
         }
     }
 
@@ -48,7 +79,7 @@ impl Channel {
         // calculate how much space is left in the buffer
         let read = self.read.load(Ordering::Relaxed);
         let write = self.write.load(Ordering::Acquire);
-        let available = available_buffer_size(read, write);
+        let available = available_buffer_size(read, write, self.size);
 
         // abort if buffer is full
         if available == 0 {
@@ -59,10 +90,12 @@ impl Channel {
     }
 
     fn nonblocking_write(&self, bytes: &[u8]) -> usize {
+        // NOTE unread data is never overwritten; what doesn't fit is dropped by the caller
+        let read = self.read.load(Ordering::Relaxed);
         let write = self.write.load(Ordering::Acquire);
+        let available = available_buffer_size(read, write, self.size);
 
-        // NOTE truncate at BUF_SIZE to avoid more than one "wrap-around" in a single `write` call
-        self.write_impl(bytes, write, BUF_SIZE)
+        self.write_impl(bytes, write, available)
     }
 
     fn write_impl(&self, bytes: &[u8], cursor: usize, available: usize) -> usize {
@@ -70,9 +103,9 @@ impl Channel {
 
         // copy `bytes[..len]` to the RTT buffer
         unsafe {
-            if cursor + len > BUF_SIZE {
+            if cursor + len > self.size {
                 // split memcpy
-                let pivot = BUF_SIZE - cursor;
+                let pivot = self.size - cursor;
                 ptr::copy_nonoverlapping(bytes.as_ptr(), self.buffer.add(cursor), pivot);
                 ptr::copy_nonoverlapping(bytes.as_ptr().add(pivot), self.buffer, len - pivot);
             } else {
@@ -83,7 +116,7 @@ impl Channel {
 
         // adjust the write pointer, so the host knows that there is new data
         self.write
-            .store(cursor.wrapping_add(len) % BUF_SIZE, Ordering::Release);
+            .store(cursor.wrapping_add(len) % self.size, Ordering::Release);
 
         // return the number of bytes written
         len
@@ -101,6 +134,42 @@ impl Channel {
         while read() != write() {}
     }
 
+    /// Are there bytes sent by the host on a down channel which weren't consumed yet?
+    #[cfg(feature = "down-channel")]
+    pub fn has_pending(&self) -> bool {
+        self.read.load(Ordering::Relaxed) != self.write.load(Ordering::Relaxed)
+    }
+
+    /// Copy the bytes sent by the host on a down channel to `buf`, without consuming them.
+    ///
+    /// Returns the number of bytes copied.
+    #[cfg(feature = "down-channel")]
+    pub fn peek(&self, buf: &mut [u8]) -> usize {
+        let read = self.read.load(Ordering::Relaxed);
+        let write = self.write.load(Ordering::Acquire);
+        let pending = if write >= read {
+            write - read
+        } else {
+            self.size - read + write
+        };
+        let len = pending.min(buf.len());
+
+        for (i, byte) in buf[..len].iter_mut().enumerate() {
+            // safety: `(read + i) % size` is within the buffer
+            *byte = unsafe { self.buffer.add((read + i) % self.size).read_volatile() };
+        }
+
+        len
+    }
+
+    /// Mark `len` bytes of a down channel as read, so the host can reuse them.
+    #[cfg(feature = "down-channel")]
+    pub fn consume(&self, len: usize) {
+        let read = self.read.load(Ordering::Relaxed);
+        self.read
+            .store(read.wrapping_add(len) % self.size, Ordering::Release);
+    }
+
     fn host_is_connected(&self) -> bool {
         // we assume that a host is connected if we are in blocking-mode. this is what probe-run does.
         self.flags.load(Ordering::Relaxed) & MODE_MASK == MODE_BLOCK_IF_FULL
@@ -108,12 +177,12 @@ impl Channel {
 }
 
 /// How much space is left in the buffer?
-fn available_buffer_size(read_cursor: usize, write_cursor: usize) -> usize {
+fn available_buffer_size(read_cursor: usize, write_cursor: usize, size: usize) -> usize {
     if read_cursor > write_cursor {
         read_cursor - write_cursor - 1
     } else if read_cursor == 0 {
-        BUF_SIZE - write_cursor - 1
+        size - write_cursor - 1
     } else {
-        BUF_SIZE - write_cursor
+        size - write_cursor
     }
 }
@@ -0,0 +1,178 @@
+//! Runtime log filter, controlled by the host through the down channel.
+//!
+//! The host sends commands on the "defmt" down channel. Each command starts with a one-byte
+//! opcode:
+//!
+//! - `[0x01, level]`: drop frames below `level`, where `0` is trace, `1` debug, `2` info, `3`
+//!   warn, `4` error and `5` drops all frames.
+//! - `[0x02, len, prefix @ ..len]`: drop frames logged from the module `prefix` and its
+//!   submodules (e.g. `my_app::radio`).
+//! - `[0x03, len, prefix @ ..len]`: stop dropping frames of a module muted by `0x02`.
+//!
+//! Commands are processed the next time a log statement is reached. An unknown opcode discards
+//! all pending bytes.
+//!
+//! At most 4 modules can be muted at once; further `0x02` commands are ignored until one of them
+//! is unmuted. Prefixes are limited to 32 bytes, and `0x02` commands with a longer one are
+//! ignored as well.
+
+use core::{
+    cell::RefCell,
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
+};
+
+use critical_section::Mutex;
+use defmt::Level;
+
+use crate::{channel::Channel, _SEGGER_RTT, DOWN_BUF_SIZE};
+
+const CMD_SET_MIN_LEVEL: u8 = 0x01;
+const CMD_MUTE: u8 = 0x02;
+const CMD_UNMUTE: u8 = 0x03;
+
+/// Value of the minimum level which drops all frames
+const LEVEL_OFF: u8 = 5;
+
+/// Maximum number of muted module prefixes
+const MAX_MUTED: usize = 4;
+
+/// Maximum length of a muted module prefix
+const MAX_PREFIX_LEN: usize = 32;
+
+/// The minimum level of the frames which are logged
+static MIN_LEVEL: AtomicU8 = AtomicU8::new(Level::Trace as u8);
+
+/// Whether any module is muted, so that the muted prefixes are only checked if there are any
+static ANY_MUTED: AtomicBool = AtomicBool::new(false);
+
+static FILTER: Mutex<RefCell<Filter>> = Mutex::new(RefCell::new(Filter::new()));
+
+/// Called by the defmt logging macros before acquiring the logger.
+///
+/// It only takes a critical section if the host sent a command, or if a module is muted.
+#[no_mangle]
+fn _defmt_filter(level: Level, module_path: &str) -> bool {
+    let channel = &_SEGGER_RTT.down_channels[0];
+    if channel.has_pending() {
+        critical_section::with(|cs| FILTER.borrow_ref_mut(cs).poll(channel));
+    }
+
+    if (level as u8) < MIN_LEVEL.load(Ordering::Relaxed) {
+        return false;
+    }
+    if !ANY_MUTED.load(Ordering::Relaxed) {
+        return true;
+    }
+    critical_section::with(|cs| !FILTER.borrow_ref(cs).is_muted(module_path))
+}
+
+struct Filter {
+    muted: [Prefix; MAX_MUTED],
+}
+
+#[derive(Clone, Copy)]
+struct Prefix {
+    /// `0` for an unused slot
+    len: usize,
+    bytes: [u8; MAX_PREFIX_LEN],
+}
+
+impl Prefix {
+    const EMPTY: Prefix = Prefix {
+        len: 0,
+        bytes: [0; MAX_PREFIX_LEN],
+    };
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    /// Does `module_path` equal this prefix, or is it one of its submodules?
+    fn matches(&self, module_path: &str) -> bool {
+        let module_path = module_path.as_bytes();
+        self.len != 0
+            && module_path.starts_with(self.as_bytes())
+            && (module_path.len() == self.len || module_path[self.len..].starts_with(b"::"))
+    }
+}
+
+impl Filter {
+    const fn new() -> Filter {
+        Filter {
+            muted: [Prefix::EMPTY; MAX_MUTED],
+        }
+    }
+
+    fn is_muted(&self, module_path: &str) -> bool {
+        self.muted.iter().any(|p| p.matches(module_path))
+    }
+
+    /// Process the commands pending on the down `channel`.
+    fn poll(&mut self, channel: &Channel) {
+        let mut buf = [0; DOWN_BUF_SIZE];
+        let len = channel.peek(&mut buf);
+
+        let mut consumed = 0;
+        while let Some(n) = self.handle_command(&buf[consumed..len]) {
+            consumed += n;
+        }
+        if consumed != 0 {
+            channel.consume(consumed);
+        }
+    }
+
+    /// Handle the first command of `bytes`.
+    ///
+    /// Returns the number of bytes consumed, or `None` if no complete command is pending.
+    fn handle_command(&mut self, bytes: &[u8]) -> Option<usize> {
+        match *bytes {
+            [] => None,
+            [CMD_SET_MIN_LEVEL, level, ..] => {
+                MIN_LEVEL.store(level.min(LEVEL_OFF), Ordering::Relaxed);
+                Some(2)
+            }
+            [CMD_SET_MIN_LEVEL] => None,
+            [CMD_MUTE | CMD_UNMUTE, len, ref rest @ ..] => {
+                let len = usize::from(len);
+                // the ring buffer holds at most `DOWN_BUF_SIZE - 1` bytes
+                if len + 2 >= DOWN_BUF_SIZE {
+                    // this command can never be complete; discard everything
+                    return Some(bytes.len());
+                }
+                let prefix = rest.get(..len)?;
+                if bytes[0] == CMD_MUTE {
+                    self.mute(prefix);
+                } else {
+                    self.unmute(prefix);
+                }
+                Some(len + 2)
+            }
+            [CMD_MUTE | CMD_UNMUTE] => None,
+            _ => Some(bytes.len()),
+        }
+    }
+
+    fn mute(&mut self, prefix: &[u8]) {
+        if prefix.is_empty() || prefix.len() > MAX_PREFIX_LEN {
+            return;
+        }
+        if self.muted.iter().any(|p| p.as_bytes() == prefix) {
+            return;
+        }
+        if let Some(slot) = self.muted.iter_mut().find(|p| p.len == 0) {
+            slot.bytes[..prefix.len()].copy_from_slice(prefix);
+            slot.len = prefix.len();
+            ANY_MUTED.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn unmute(&mut self, prefix: &[u8]) {
+        for slot in self.muted.iter_mut() {
+            if slot.len != 0 && slot.as_bytes() == prefix {
+                *slot = Prefix::EMPTY;
+            }
+        }
+        let any_muted = self.muted.iter().any(|p| p.len != 0);
+        ANY_MUTED.store(any_muted, Ordering::Relaxed);
+    }
+}
@@ -23,6 +23,52 @@
 //! If losing data is not an concern you can disable blocking mode by enabling
 //! the feature `disable-blocking-mode`
 //!
+//! In non-blocking mode, frames which don't fit in the RTT buffer are dropped
+//! (or truncated). The number of dropped frames is counted, and reported to the
+//! host in a "N frames dropped" frame as soon as there is room for it again.
+//!
+//! # Fingerprint
+//!
+//! If the firmware has a fingerprint (see `defmt::fingerprint!`), it is sent
+//! to the host in a handshake frame before the first frame of every up
+//! channel, so that the host can check that it decodes the logs with the
+//! right ELF file. Call [`send_fingerprint`] to send it again, e.g.
+//! periodically for hosts which attach to an already-running device.
+//!
+//! # Multiple channels
+//!
+//! By default all frames are written to a single up channel named "defmt".
+//! Additional up channels can be configured at build time with the
+//! `DEFMT_RTT_EXTRA_CHANNELS` environment variable, as a comma-separated list
+//! of `name:size[:levels]` descriptions. `levels` is an optional `+`-separated
+//! list of log levels whose frames are routed to that channel:
+//!
+//! ```text
+//! DEFMT_RTT_EXTRA_CHANNELS="trace:4096:trace+debug"
+//! ```
+//!
+//! Extra channels are numbered from `1` in the order they are listed, channel
+//! `0` being the "defmt" channel. Frames can also be routed explicitly with
+//! [`with_channel`].
+//!
+//! Every channel carries an independent defmt stream, so the host has to
+//! decode each of them on its own. With the `sequence-numbers` feature of
+//! `defmt`, every channel also has its own frame counter, so that the frames
+//! routed to the other channels are not reported as lost. Extra channels
+//! always start in non-blocking mode: frames routed to a channel which is not
+//! read by the host do not stall the program.
+//!
+//! # Runtime filtering
+//!
+//! With the `down-channel` feature, the RTT header also declares a "defmt"
+//! down channel. The host can send commands on it to change, at runtime, which
+//! frames are logged: set a minimum log level, and mute or unmute the modules
+//! starting with a given prefix. The filter is applied by the logging macros
+//! before the logger is acquired, on top of the `DEFMT_LOG` filter. See the
+//! `filter` module for the command format.
+//!
+//! This feature enables the `runtime-filter` feature of `defmt`.
+//!
 //! # Critical section implementation
 //!
 //! This crate uses
@@ -43,13 +89,21 @@
 
 mod channel;
 mod consts;
+#[cfg(feature = "down-channel")]
+mod filter;
 
 use core::{
     cell::UnsafeCell,
     sync::atomic::{AtomicBool, AtomicUsize, Ordering},
 };
 
-use crate::{channel::Channel, consts::BUF_SIZE};
+use crate::{
+    channel::Channel,
+    consts::{BUF_SIZE, LEVEL_ROUTES, UP_CHANNELS},
+};
+
+// see `build.rs` for contents
+include!(concat!(env!("OUT_DIR"), "/extra_channels.rs"));
 
 /// The relevant bits in the mode field in the Header
 const MODE_MASK: usize = 0b11;
@@ -60,6 +114,38 @@ const MODE_BLOCK_IF_FULL: usize = 2;
 /// Don't block if the RTT buffer is full. Truncate data to output as much as fits.
 const MODE_NON_BLOCKING_TRIM: usize = 1;
 
+/// Number of down channels
+const DOWN_CHANNELS: usize = if cfg!(feature = "down-channel") { 1 } else { 0 };
+
+/// Size of the down channel buffer; bounds the length of a command
+#[cfg(feature = "down-channel")]
+const DOWN_BUF_SIZE: usize = 64;
+
+/// Index of the frames reporting dropped frames.
+///
+/// The linker script keeps the indices of interned strings below this value.
+const DROPPED_FRAMES_INDEX: u16 = 0xFFFF;
+
+/// Space needed to report dropped frames: an empty frame terminating the
+/// truncated frame, then the `[index, count: u32]` frame, both encoded. Covers
+/// the worst case of every encoding, e.g. HDLC escaping every byte.
+const DROPPED_FRAMES_REPORT_LEN: usize = 32;
+
+/// Index of the handshake frames carrying the fingerprint of the firmware.
+///
+/// The linker script keeps the indices of interned strings below this value.
+const FINGERPRINT_INDEX: u16 = 0xFFFE;
+
+/// Space needed to send the `[index, fingerprint: u64]` frame, encoded. Covers
+/// the worst case of every encoding, e.g. HDLC escaping every byte.
+const FINGERPRINT_FRAME_LEN: usize = 32;
+
+/// Value of [`TARGET_CHANNEL`] when frames are routed by level
+const NO_TARGET_CHANNEL: usize = usize::MAX;
+
+/// The up channel selected by [`with_channel`]
+static TARGET_CHANNEL: AtomicUsize = AtomicUsize::new(NO_TARGET_CHANNEL);
+
 /// The defmt global logger
 ///
 /// The defmt crate requires that this be a unit type, so our state is stored in
@@ -81,16 +167,18 @@ static RTT_ENCODER: RttEncoder = RttEncoder::new();
 #[no_mangle]
 static _SEGGER_RTT: Header = Header {
     id: *b"SEGGER RTT\0\0\0\0\0\0",
-    max_up_channels: 1,
-    max_down_channels: 0,
-    up_channel: Channel {
-        name: NAME.as_ptr(),
-        buffer: BUFFER.get(),
-        size: BUF_SIZE,
-        write: AtomicUsize::new(0),
-        read: AtomicUsize::new(0),
-        flags: AtomicUsize::new(MODE_NON_BLOCKING_TRIM),
-    },
+    max_up_channels: UP_CHANNELS,
+    max_down_channels: DOWN_CHANNELS,
+    // see `build.rs` for contents
+    up_channels: include!(concat!(env!("OUT_DIR"), "/up_channels.rs")),
+    #[cfg(feature = "down-channel")]
+    down_channels: [Channel::new(
+        NAME.as_ptr(),
+        DOWN_BUFFER.get(),
+        DOWN_BUF_SIZE,
+    )],
+    #[cfg(not(feature = "down-channel"))]
+    down_channels: [],
 };
 
 /// Report whether the SEGGER RTT up channel is in blocking mode.
@@ -101,13 +189,74 @@ static _SEGGER_RTT: Header = Header {
 /// Currently we start-up in non-blocking mode, so if it's been set to blocking
 /// mode then the connected client (e.g. probe-rs) must have done it.
 pub fn in_blocking_mode() -> bool {
-    (_SEGGER_RTT.up_channel.flags.load(Ordering::Relaxed) & MODE_MASK) == MODE_BLOCK_IF_FULL
+    (_SEGGER_RTT.up_channels[0].flags.load(Ordering::Relaxed) & MODE_MASK) == MODE_BLOCK_IF_FULL
+}
+
+/// Route all frames logged while `f` runs to the up channel number `channel`.
+///
+/// Channel `0` is the "defmt" channel, the channels configured with
+/// `DEFMT_RTT_EXTRA_CHANNELS` are numbered from `1`. This takes precedence over
+/// the routing by level. Note that it also applies to frames logged by
+/// interrupt handlers which preempt `f`.
+///
+/// Panics if `channel` does not exist.
+pub fn with_channel<R>(channel: usize, f: impl FnOnce() -> R) -> R {
+    if channel >= UP_CHANNELS {
+        panic!("defmt-rtt up channel {} does not exist", channel)
+    }
+
+    let previous = critical_section::with(|_| {
+        let previous = TARGET_CHANNEL.load(Ordering::Relaxed);
+        TARGET_CHANNEL.store(channel, Ordering::Relaxed);
+        previous
+    });
+    let ret = f();
+    TARGET_CHANNEL.store(previous, Ordering::Relaxed);
+    ret
+}
+
+/// Select the up channel for a frame, from the first bytes written to it.
+fn select_channel(bytes: &[u8]) -> usize {
+    let target = TARGET_CHANNEL.load(Ordering::Relaxed);
+    if target != NO_TARGET_CHANNEL {
+        return target;
+    }
+
+    if UP_CHANNELS == 1 {
+        return 0;
+    }
+
+    // every frame starts with the index of its interned format string, and the
+    // indices of log statements are grouped by level by the linker script
+    let Some(index) = defmt::export::decode_index(bytes) else {
+        return 0;
+    };
+    let ranges = defmt::IdRanges::get();
+    [
+        ranges.trace,
+        ranges.debug,
+        ranges.info,
+        ranges.warn,
+        ranges.error,
+    ]
+    .iter()
+    .position(|range| range.contains(&index))
+    .map_or(0, |level| LEVEL_ROUTES[level])
 }
 
 /// Our shared buffer
 #[cfg_attr(target_os = "macos", link_section = ".uninit,defmt-rtt.BUFFER")]
 #[cfg_attr(not(target_os = "macos"), link_section = ".uninit.defmt-rtt.BUFFER")]
-static BUFFER: Buffer = Buffer::new();
+static BUFFER: Buffer<BUF_SIZE> = Buffer::new();
+
+/// Our shared buffer for commands sent by the host
+#[cfg(feature = "down-channel")]
+#[cfg_attr(target_os = "macos", link_section = ".uninit,defmt-rtt.DOWN_BUFFER")]
+#[cfg_attr(
+    not(target_os = "macos"),
+    link_section = ".uninit.defmt-rtt.DOWN_BUFFER"
+)]
+static DOWN_BUFFER: Buffer<DOWN_BUF_SIZE> = Buffer::new();
 
 /// The name of our channel.
 ///
@@ -125,8 +274,22 @@ struct RttEncoder {
     taken: AtomicBool,
     /// We need to remember this to exit a critical section
     cs_restore: UnsafeCell<critical_section::RestoreState>,
-    /// A defmt::Encoder for encoding frames
-    encoder: UnsafeCell<defmt::Encoder>,
+    /// The up channel of the current frame
+    ///
+    /// Is `None` until the first `write` of the frame, which selects it.
+    channel: UnsafeCell<Option<usize>>,
+    /// Is `true` when the current frame did not fit in its up channel
+    ///
+    /// The rest of the frame is then dropped.
+    truncated: UnsafeCell<bool>,
+    /// The number of frames dropped and not reported yet, for each up channel
+    dropped: [UnsafeCell<u32>; UP_CHANNELS],
+    /// The sequence number of the next frame, for each up channel
+    sequence_numbers: [UnsafeCell<u8>; UP_CHANNELS],
+    /// Whether the fingerprint was sent, for each up channel
+    fingerprint_sent: [AtomicBool; UP_CHANNELS],
+    /// A defmt::Encoder for encoding frames, for each up channel
+    encoders: [UnsafeCell<defmt::Encoder>; UP_CHANNELS],
 }
 
 impl RttEncoder {
@@ -135,7 +298,12 @@ impl RttEncoder {
         RttEncoder {
             taken: AtomicBool::new(false),
             cs_restore: UnsafeCell::new(critical_section::RestoreState::invalid()),
-            encoder: UnsafeCell::new(defmt::Encoder::new()),
+            channel: UnsafeCell::new(None),
+            truncated: UnsafeCell::new(false),
+            dropped: [const { UnsafeCell::new(0) }; UP_CHANNELS],
+            sequence_numbers: [const { UnsafeCell::new(0) }; UP_CHANNELS],
+            fingerprint_sent: [const { AtomicBool::new(false) }; UP_CHANNELS],
+            encoders: [const { UnsafeCell::new(defmt::Encoder::new()) }; UP_CHANNELS],
         }
     }
 
@@ -157,10 +325,6 @@ impl RttEncoder {
         // section.
         unsafe {
             self.cs_restore.get().write(restore);
-            let encoder: &mut defmt::Encoder = &mut *self.encoder.get();
-            encoder.start_frame(|b| {
-                _SEGGER_RTT.up_channel.write_all(b);
-            });
         }
     }
 
@@ -173,10 +337,132 @@ impl RttEncoder {
         // safety: accessing the cell is OK because we have acquired a critical
         // section.
         unsafe {
-            let encoder: &mut defmt::Encoder = &mut *self.encoder.get();
+            let channel = match self.channel.get().read() {
+                Some(channel) => channel,
+                // first write of the frame: the frame is started on the selected channel
+                None => {
+                    let channel = select_channel(bytes);
+                    self.channel.get().write(Some(channel));
+                    if self.report_dropped_frames(channel) {
+                        self.send_fingerprint(channel);
+                        let encoder: &mut defmt::Encoder = &mut *self.encoders[channel].get();
+                        let mut complete = true;
+                        encoder.start_frame(|b| {
+                            complete = complete && _SEGGER_RTT.up_channels[channel].write_all(b);
+                        });
+                        self.truncated.get().write(!complete);
+                    } else {
+                        self.truncated.get().write(true);
+                    }
+                    channel
+                }
+            };
+            if self.truncated.get().read() {
+                return;
+            }
+            let encoder: &mut defmt::Encoder = &mut *self.encoders[channel].get();
+            let mut complete = true;
             encoder.write(bytes, |b| {
-                _SEGGER_RTT.up_channel.write_all(b);
+                complete = complete && _SEGGER_RTT.up_channels[channel].write_all(b);
             });
+            self.truncated.get().write(!complete);
+        }
+    }
+
+    /// Report the frames dropped on up channel `channel`, if any.
+    ///
+    /// Returns `false` if there is not enough space in the channel to do so; the
+    /// next frame must then be dropped as well.
+    ///
+    /// # Safety
+    ///
+    /// Do not call unless you have called `acquire`.
+    unsafe fn report_dropped_frames(&self, channel: usize) -> bool {
+        // safety: accessing the cells is OK because we have acquired a critical
+        // section.
+        unsafe {
+            let dropped = &mut *self.dropped[channel].get();
+            if *dropped == 0 {
+                return true;
+            }
+
+            let up_channel = &_SEGGER_RTT.up_channels[channel];
+            if up_channel.free_space() < DROPPED_FRAMES_REPORT_LEN {
+                return false;
+            }
+
+            let encoder: &mut defmt::Encoder = &mut *self.encoders[channel].get();
+            let write = |b: &[u8]| {
+                up_channel.write_all(b);
+            };
+            // the separator of the last truncated frame was dropped too; an empty
+            // frame lets the host resynchronize
+            encoder.end_frame(write);
+            encoder.start_frame(write);
+            encoder.write(
+                defmt::export::encode_index(DROPPED_FRAMES_INDEX, &mut [0; 3]),
+                write,
+            );
+            encoder.write(&dropped.to_le_bytes(), write);
+            encoder.end_frame(write);
+            *dropped = 0;
+            true
+        }
+    }
+
+    /// Send the fingerprint of the firmware on up channel `channel`, if it
+    /// wasn't sent there yet.
+    ///
+    /// If there is not enough space in the channel, it is sent before one of
+    /// the next frames instead.
+    ///
+    /// # Safety
+    ///
+    /// Do not call unless you have called `acquire`.
+    unsafe fn send_fingerprint(&self, channel: usize) {
+        let fingerprint = defmt::export::fingerprint();
+        let sent = &self.fingerprint_sent[channel];
+        if fingerprint == 0 || sent.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let up_channel = &_SEGGER_RTT.up_channels[channel];
+        if up_channel.free_space() < FINGERPRINT_FRAME_LEN {
+            return;
+        }
+
+        // safety: accessing the cell is OK because we have acquired a critical
+        // section.
+        let encoder: &mut defmt::Encoder = unsafe { &mut *self.encoders[channel].get() };
+        let write = |b: &[u8]| {
+            up_channel.write_all(b);
+        };
+        encoder.start_frame(write);
+        encoder.write(
+            defmt::export::encode_index(FINGERPRINT_INDEX, &mut [0; 3]),
+            write,
+        );
+        encoder.write(&fingerprint.to_le_bytes(), write);
+        encoder.end_frame(write);
+        sent.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns the sequence number of the current frame, counted separately on
+    /// each up channel.
+    ///
+    /// # Safety
+    ///
+    /// Do not call unless you have called `acquire`.
+    unsafe fn next_sequence_number(&self) -> u8 {
+        // safety: accessing the cells is OK because we have acquired a critical
+        // section.
+        unsafe {
+            // the channel was selected by the first write, of the format string index
+            let channel = self.channel.get().read().unwrap_or(0);
+            let sequence_number = &mut *self.sequence_numbers[channel].get();
+            let n = *sequence_number;
+            *sequence_number = n.wrapping_add(1);
+            n
         }
     }
 
@@ -188,7 +474,9 @@ impl RttEncoder {
     unsafe fn flush(&self) {
         // safety: accessing the `&'static _` is OK because we have acquired a
         // critical section.
-        _SEGGER_RTT.up_channel.flush();
+        for channel in &_SEGGER_RTT.up_channels {
+            channel.flush();
+        }
     }
 
     /// Release the defmt encoder.
@@ -206,10 +494,25 @@ impl RttEncoder {
         // safety: accessing the cell is OK because we have acquired a critical
         // section.
         unsafe {
-            let encoder: &mut defmt::Encoder = &mut *self.encoder.get();
-            encoder.end_frame(|b| {
-                _SEGGER_RTT.up_channel.write_all(b);
-            });
+            // frames without any data (e.g. from `defmt::flush`) were never started
+            if let Some(channel) = self.channel.get().read() {
+                self.channel.get().write(None);
+                let encoder: &mut defmt::Encoder = &mut *self.encoders[channel].get();
+                let mut complete = !self.truncated.get().read();
+                if complete {
+                    encoder.end_frame(|b| {
+                        complete = complete && _SEGGER_RTT.up_channels[channel].write_all(b);
+                    });
+                } else {
+                    // only reset the encoder; the frame is terminated when the
+                    // dropped frames are reported
+                    encoder.end_frame(|_| {});
+                }
+                if !complete {
+                    let dropped = &mut *self.dropped[channel].get();
+                    *dropped = dropped.saturating_add(1);
+                }
+            }
             let restore = self.cs_restore.get().read();
             self.taken.store(false, Ordering::Relaxed);
             // paired with exactly one acquire call
@@ -220,6 +523,24 @@ impl RttEncoder {
 
 unsafe impl Sync for RttEncoder {}
 
+/// Send the fingerprint of the firmware again, before the next frame of every
+/// up channel.
+///
+/// Does nothing if the firmware has no fingerprint.
+pub fn send_fingerprint() {
+    for sent in &RTT_ENCODER.fingerprint_sent {
+        sent.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Called by defmt for the sequence number of every frame, with the
+/// `sequence-numbers` feature.
+#[no_mangle]
+fn _defmt_sequence_number() -> u8 {
+    // safety: defmt only calls this while the logger is acquired
+    unsafe { RTT_ENCODER.next_sequence_number() }
+}
+
 unsafe impl defmt::Logger for Logger {
     fn acquire() {
         RTT_ENCODER.acquire();
@@ -249,19 +570,20 @@ struct Header {
     id: [u8; 16],
     max_up_channels: usize,
     max_down_channels: usize,
-    up_channel: Channel,
+    up_channels: [Channel; UP_CHANNELS],
+    down_channels: [Channel; DOWN_CHANNELS],
 }
 
 unsafe impl Sync for Header {}
 
-struct Buffer {
-    inner: UnsafeCell<[u8; BUF_SIZE]>,
+struct Buffer<const N: usize> {
+    inner: UnsafeCell<[u8; N]>,
 }
 
-impl Buffer {
-    const fn new() -> Buffer {
+impl<const N: usize> Buffer<N> {
+    const fn new() -> Buffer<N> {
         Buffer {
-            inner: UnsafeCell::new([0; BUF_SIZE]),
+            inner: UnsafeCell::new([0; N]),
         }
     }
 
@@ -270,4 +592,4 @@ impl Buffer {
     }
 }
 
-unsafe impl Sync for Buffer {}
+unsafe impl<const N: usize> Sync for Buffer<N> {}
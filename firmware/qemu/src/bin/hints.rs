@@ -146,6 +146,39 @@ fn main() -> ! {
     defmt::info!("Time ms {:tms}", 1_234_567_u64);
     defmt::info!("Time us {:tus}", 1_234_567_u64);
 
+    // Variant names of fieldless enums
+    #[derive(defmt::Format)]
+    #[allow(dead_code)]
+    enum Mode {
+        Off,
+        Sleep = 3,
+        Run,
+    }
+    defmt::info!("Mode {=u8:enum(Mode)}", 4_u8);
+    defmt::info!("Mode {=u8:enum(Mode)}", 7_u8);
+    defmt::info!("Mode {=u8:enum(hints::Mode)}", 4_u8);
+
+    // Scaled integers
+    defmt::info!("Q15   {=i16:q15}", -16384_i16);
+    defmt::info!("Fixed {=u32:fixed(3)}", 3300_u32);
+    defmt::info!("Unit  {=u32:mV}", 3300_u32);
+
+    // Width, alignment and precision
+    defmt::info!("Float [{=f32:.2}] [{:.1}]", 3.14159_f32, 2.5_f32);
+    defmt::info!("Str   [{=str:>6}] [{=str:*<6}]", "abc", "abc");
+    defmt::info!("Width [{:6}] [{:<6}]", 42_u8, 42_u8);
+
+    // Raw bits and scientific notation of floats
+    defmt::info!("Bits  {=f32:#x} {=f64:x}", 1.0_f32, -2.0_f64);
+    defmt::info!("Sci   {=f32:e} {=f64:.2E}", 1500.0_f32, 0.000123_f64);
+    defmt::info!("NaN   {=f32} {=f32}", f32::NAN, f32::from_bits(0xffc0_0001));
+
+    // Hexdump
+    defmt::info!(
+        "Dump {=[u8]:hexdump}",
+        &b"Hello, world!\x00\x01\x02\x03\x04\x05\x06"[..]
+    );
+
     loop {
         debug::exit(debug::EXIT_SUCCESS)
     }
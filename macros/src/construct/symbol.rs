@@ -28,6 +28,8 @@ struct Symbol<'a> {
     ///   wire format), and `NUM` is the number of defined bitflag values.
     /// * `defmt_bitflags_value` marks a `static` that holds the value of a bitflags `const`, its
     ///   data field is `STRUCT_NAME::FLAG_NAME`.
+    /// * `defmt_enum_value` marks a `static` that holds the discriminant of a variant of a
+    ///   fieldless enum deriving `Format`, its data field is `ENUM_NAME::INDEX::VARIANT_NAME`.
     /// * Anything starting with `defmt_` is reserved for use by defmt, other prefixes are free for
     ///   use by third-party apps (but they all should use a prefix!).
     tag: String,
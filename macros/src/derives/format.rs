@@ -43,6 +43,12 @@ pub(crate) fn expand(input: TokenStream) -> TokenStream {
         Err(e) => return e.into_compile_error().into(),
     };
 
+    // the variants of generic enums can't be named in a `static`
+    let enum_values = match &data {
+        Data::Enum(data) if generics.params.is_empty() => codegen::enum_values(&ident, data),
+        _ => quote!(),
+    };
+
     let codegen::Generics {
         impl_generics,
         type_generics,
@@ -50,6 +56,8 @@ pub(crate) fn expand(input: TokenStream) -> TokenStream {
     } = codegen::Generics::codegen(&mut generics, where_predicates);
 
     quote!(
+        #enum_values
+
         #[automatically_derived]
         impl #impl_generics #defmt_path::Format for #ident #type_generics #where_clause {
             fn format(&self, f: #defmt_path::Formatter) {
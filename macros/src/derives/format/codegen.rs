@@ -4,7 +4,7 @@ use syn::{
     parse_quote, DataStruct, Ident, ImplGenerics, TypeGenerics, WhereClause, WherePredicate,
 };
 
-pub(crate) use enum_data::encode as encode_enum_data;
+pub(crate) use enum_data::{encode as encode_enum_data, values as enum_values};
 
 use crate::construct;
 
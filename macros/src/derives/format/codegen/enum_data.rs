@@ -1,7 +1,7 @@
 use proc_macro2::TokenStream as TokenStream2;
 use proc_macro_error2::abort_call_site;
-use quote::quote;
-use syn::{DataEnum, Ident};
+use quote::{format_ident, quote};
+use syn::{DataEnum, Fields, Ident};
 
 use crate::construct;
 
@@ -70,6 +70,43 @@ pub(crate) fn encode(
     })
 }
 
+/// Generates a `static` holding the discriminant of each variant of a fieldless enum, so that the
+/// decoder can print the name of a variant for a raw integer with the `enum(NAME)` display hint.
+///
+/// Returns nothing for enums with fields, whose variants have no integer value.
+pub(crate) fn values(ident: &Ident, data: &DataEnum) -> TokenStream2 {
+    if data.variants.is_empty()
+        || !data
+            .variants
+            .iter()
+            .all(|variant| matches!(variant.fields, Fields::Unit))
+    {
+        return quote!();
+    }
+
+    let statics = data.variants.iter().enumerate().map(|(index, variant)| {
+        let variant_ident = &variant.ident;
+        let var_name = format_ident!("VALUE_{}", index);
+        let sym_name = construct::mangled_symbol_name(
+            "enum_value",
+            &format!("{ident}::{index}::{variant_ident}"),
+        );
+
+        quote!(
+            #[cfg_attr(target_os = "macos", link_section = ".defmt,end")]
+            #[cfg_attr(not(target_os = "macos"), link_section = ".defmt.end")]
+            #[export_name = #sym_name]
+            static #var_name: i128 = #ident::#variant_ident as i128;
+        )
+    });
+
+    quote!(
+        const _: () = {
+            #(#statics)*
+        };
+    )
+}
+
 enum DiscriminantEncoder {
     Nop,
     U8,
@@ -1,9 +1,9 @@
-use defmt_parser::{Level, ParserMode};
+use defmt_parser::{Level, ParserMode, FIELD_SEPARATOR};
 use proc_macro::TokenStream;
-use proc_macro2::TokenStream as TokenStream2;
+use proc_macro2::{Span, TokenStream as TokenStream2};
 use proc_macro_error2::abort;
-use quote::quote;
-use syn::{parse_macro_input, parse_quote};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, spanned::Spanned, Expr, Ident};
 
 use crate::construct;
 
@@ -19,7 +19,14 @@ pub(crate) fn expand(level: Level, args: TokenStream) -> TokenStream {
 }
 
 pub(crate) fn expand_parsed(level: Level, args: Args) -> TokenStream2 {
-    let format_string = args.format_string.value();
+    let mut format_string = args.format_string.value();
+    if format_string.contains(FIELD_SEPARATOR) {
+        abort!(
+            args.format_string,
+            "format string must not contain the character {:?}",
+            FIELD_SEPARATOR
+        )
+    }
     let fragments = match defmt_parser::parse(&format_string, ParserMode::Strict) {
         Ok(args) => args,
         Err(e) => abort!(args.format_string, "{}", e),
@@ -29,13 +36,29 @@ pub(crate) fn expand_parsed(level: Level, args: Args) -> TokenStream2 {
         .formatting_args
         .map(|punctuated| punctuated.into_iter().collect::<Vec<_>>())
         .unwrap_or_default();
+    let (mut formatting_exprs, fields) = split_fields(formatting_exprs);
 
-    let Codegen { patterns, exprs } = Codegen::new(
+    let Codegen {
+        mut patterns,
+        mut exprs,
+    } = Codegen::new(
         &fragments,
         formatting_exprs.len(),
         args.format_string.span(),
     );
 
+    // fields are appended to the format string, so that their names get interned with it, and
+    // their values are encoded after the positional arguments
+    for (name, value) in fields {
+        let index = formatting_exprs.len();
+        format_string.push_str(&format!("{FIELD_SEPARATOR}{name}={{{index}=?}}"));
+
+        let arg = format_ident!("arg{}", index);
+        exprs.push(quote!(defmt::export::fmt(#arg)));
+        patterns.push(arg);
+        formatting_exprs.push(value);
+    }
+
     let header = construct::interned_string(
         &format_string,
         level.as_str(),
@@ -44,6 +67,16 @@ pub(crate) fn expand_parsed(level: Level, args: Args) -> TokenStream2 {
         &parse_quote!(defmt),
     );
     let env_filter = EnvFilter::from_env_var();
+    let level_variant = Ident::new(
+        match level {
+            Level::Trace => "Trace",
+            Level::Debug => "Debug",
+            Level::Info => "Info",
+            Level::Warn => "Warn",
+            Level::Error => "Error",
+        },
+        Span::call_site(),
+    );
 
     if let Some(filter_check) = env_filter.path_check(level) {
         let content = if exprs.is_empty() {
@@ -65,7 +98,9 @@ pub(crate) fn expand_parsed(level: Level, args: Args) -> TokenStream2 {
                 option_env!("DEFMT_LOG");
                 match (#(&(#formatting_exprs)),*) {
                     (#(#patterns),*) => {
-                        if #filter_check {
+                        if #filter_check
+                            && defmt::export::enabled(defmt::Level::#level_variant, module_path!())
+                        {
                             #content
                         }
                     }
@@ -84,3 +119,30 @@ pub(crate) fn expand_parsed(level: Level, args: Args) -> TokenStream2 {
         )
     }
 }
+
+/// Splits the trailing `key = value` arguments off the positional ones.
+fn split_fields(exprs: Vec<Expr>) -> (Vec<Expr>, Vec<(Ident, Expr)>) {
+    let mut positional = vec![];
+    let mut fields = vec![];
+    for expr in exprs {
+        match expr {
+            Expr::Assign(assign) => match &*assign.left {
+                Expr::Path(path) if path.attrs.is_empty() && path.qself.is_none() => {
+                    let Some(name) = path.path.get_ident() else {
+                        abort!(path, "field names must be identifiers")
+                    };
+                    if fields.iter().any(|(field, _)| field == name) {
+                        abort!(name, "field `{}` is already set", name)
+                    }
+                    fields.push((name.clone(), *assign.right))
+                }
+                left => abort!(left, "field names must be identifiers"),
+            },
+            expr if !fields.is_empty() => {
+                abort!(expr.span(), "positional arguments must come before fields")
+            }
+            expr => positional.push(expr),
+        }
+    }
+    (positional, fields)
+}
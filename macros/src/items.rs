@@ -1,4 +1,5 @@
 //! Procedural macros that expand to items
 
 pub(crate) mod bitflags;
+pub(crate) mod fingerprint;
 pub(crate) mod timestamp;
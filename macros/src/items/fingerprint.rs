@@ -0,0 +1,47 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash, Hasher},
+    process,
+    time::SystemTime,
+};
+
+use proc_macro::TokenStream;
+use proc_macro_error2::abort_call_site;
+use quote::quote;
+
+pub(crate) fn expand(args: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        abort_call_site!("`fingerprint!` takes no arguments");
+    }
+
+    let fingerprint = fingerprint();
+    let sym_name = format!("_defmt_fingerprint_ = {fingerprint:016x}");
+
+    quote!(
+        const _: () = {
+            // Tells the decoder which fingerprint the firmware sends.
+            #[used]
+            #[cfg_attr(target_os = "macos", link_section = ".defmt,end.FINGERPRINT")]
+            #[cfg_attr(not(target_os = "macos"), link_section = ".defmt.end")]
+            #[export_name = #sym_name]
+            static DEFMT_FINGERPRINT: u8 = 0;
+
+            // Read by the global logger, through `defmt::export::fingerprint`. The unique symbol
+            // name prevents multiple `fingerprint!` invocations in the crate graph.
+            #[export_name = "_defmt_fingerprint"]
+            static FINGERPRINT: u64 = #fingerprint;
+        };
+    )
+    .into()
+}
+
+/// Returns a value that is different for every build of the crate invoking the macro.
+///
+/// This identifies the build, not the `.defmt` table: the table is only complete once the firmware
+/// is linked, after the macro ran. `0` means "no fingerprint", so it's never returned.
+fn fingerprint() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    SystemTime::now().hash(&mut hasher);
+    process::id().hash(&mut hasher);
+    hasher.finish().max(1)
+}
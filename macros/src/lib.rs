@@ -202,6 +202,12 @@ pub fn bitflags(ts: TokenStream) -> TokenStream {
     items::bitflags::expand(ts)
 }
 
+#[proc_macro]
+#[proc_macro_error]
+pub fn fingerprint(args: TokenStream) -> TokenStream {
+    items::fingerprint::expand(args)
+}
+
 #[proc_macro]
 #[proc_macro_error]
 pub fn timestamp(args: TokenStream) -> TokenStream {
@@ -23,6 +23,10 @@ pub enum DisplayHint {
         alternate: bool,
         zero_pad: usize,
     },
+    /// `:e` OR `:E`, formats floats in scientific notation
+    Scientific {
+        uppercase: bool,
+    },
     /// `:a`
     Ascii,
     /// `:?`
@@ -41,6 +45,24 @@ pub enum DisplayHint {
         disambiguator: String,
         crate_name: Option<String>,
     },
+    /// `:qN`, e.g. `:q15`, formats integers as fixed-point numbers with N (up to 64) fractional
+    /// bits
+    QFormat(u8),
+    /// `:fixed(N)`, e.g. `:fixed(3)`, formats integers as decimal numbers with N fractional
+    /// digits, i.e. divided by 10^N
+    Fixed(u8),
+    /// `:mV`, `:kHz`, `:%` etc., formats integers followed by a unit
+    Unit(String),
+    /// `:hexdump`, formats byte slices and arrays as lines of offset, hex and ASCII like `xxd`
+    Hexdump,
+    /// `:enum(Name)` instructs the decoder to print the name of the variant of the fieldless enum
+    /// `Name` deriving `Format` whose discriminant is the value, instead of the raw value.
+    ///
+    /// `:enum(crate_name::Name)` only looks for the enum in the crate `crate_name`.
+    Enum {
+        name: String,
+        crate_name: Option<String>,
+    },
     /// `:cbor`: There is CBOR data encoded in those bytes, to be shown in diagnostic notation.
     ///
     /// Technically, the byte string interpreted as a CBOR sequence, and shown in the diagnostic
@@ -52,6 +74,12 @@ pub enum DisplayHint {
     // Should we allow additional params that give a CDDL that further guides processing (like,
     // when data is not tagged but the shape is known for processing anyway)?
     Cbor,
+    /// `core::fmt`-like fill, alignment, width and precision, e.g. `:>10`, `:*^8` or `:.2`, in
+    /// front of another display hint, e.g. `:>#10x`
+    Formatted {
+        spec: FormatSpec,
+        hint: Box<DisplayHint>,
+    },
     /// Display hints currently not supported / understood
     Unknown(String),
 }
@@ -59,7 +87,19 @@ pub enum DisplayHint {
 impl DisplayHint {
     /// Parses the display hint (e.g. the `#x` in `{=u8:#x}`)
     pub(crate) fn parse(mut s: &str) -> Option<Self> {
-        const BITFLAGS_HINT_START: &str = "__internal_bitflags_";
+        let mut spec = FormatSpec::default();
+
+        // Like in core::fmt, the fill and the alignment come first.
+        let mut chars = s.chars();
+        let (first, second) = (chars.next(), chars.next());
+        if let (Some(fill), Some(align)) = (first, second.and_then(parse_alignment)) {
+            spec.fill = fill;
+            spec.align = Some(align);
+            s = &s[fill.len_utf8() + 1..];
+        } else if let Some(align) = first.and_then(parse_alignment) {
+            spec.align = Some(align);
+            s = &s[1..];
+        }
 
         // The `#` comes before any padding hints (I think this matches core::fmt).
         // It is ignored for types that don't have an alternate representation.
@@ -78,6 +118,33 @@ impl DisplayHint {
             0 // default behavior is the same as no zero-padding.
         };
 
+        if s.starts_with(|c: char| c.is_ascii_digit()) {
+            let (rest, width) = parse_integer::<usize>(s)?;
+            s = rest;
+            spec.width = Some(width);
+        }
+
+        if let Some(rest) = s.strip_prefix('.') {
+            let (rest, precision) = parse_integer::<usize>(rest)?;
+            s = rest;
+            spec.precision = Some(precision);
+        }
+
+        let hint = Self::parse_hint(s, alternate, zero_pad)?;
+        Some(match spec == FormatSpec::default() {
+            true => hint,
+            false => DisplayHint::Formatted {
+                spec,
+                hint: Box::new(hint),
+            },
+        })
+    }
+
+    /// Parses the part of the display hint after the padding and precision (e.g. the `x` in
+    /// `{=u8:#04x}`)
+    fn parse_hint(s: &str, alternate: bool, zero_pad: usize) -> Option<Self> {
+        const BITFLAGS_HINT_START: &str = "__internal_bitflags_";
+
         if let Some(stripped) = s.strip_prefix(BITFLAGS_HINT_START) {
             let parts = stripped.split('@').collect::<Vec<_>>();
             if parts.len() < 3 || parts.len() > 4 {
@@ -92,6 +159,41 @@ impl DisplayHint {
             });
         }
 
+        if let Some(path) = s.strip_prefix("enum(").and_then(|s| s.strip_suffix(')')) {
+            let (crate_name, name) = match path.split_once("::") {
+                Some((crate_name, name)) => (Some(crate_name), name),
+                None => (None, path),
+            };
+            if !is_identifier(name) || crate_name.is_some_and(|c| !is_identifier(c)) {
+                return None;
+            }
+            return Some(DisplayHint::Enum {
+                name: name.into(),
+                crate_name: crate_name.map(str::to_string),
+            });
+        }
+
+        if let Some(digits) = s.strip_prefix("fixed(").and_then(|s| s.strip_suffix(')')) {
+            // 10^38 is the largest power of ten that fits into an `u128`
+            return match parse_integer::<u8>(digits)? {
+                ("", decimals @ 0..=38) => Some(DisplayHint::Fixed(decimals)),
+                _ => None,
+            };
+        }
+
+        if let Some(digits) = s.strip_prefix('q') {
+            // the decoder prints the fractional digits exactly, which needs 4 bits of headroom
+            // in an `u128`, so the limit is nice and round instead
+            return match parse_integer::<u8>(digits)? {
+                ("", fraction_bits @ 1..=64) => Some(DisplayHint::QFormat(fraction_bits)),
+                _ => None,
+            };
+        }
+
+        if is_unit(s) {
+            return Some(DisplayHint::Unit(s.into()));
+        }
+
         Some(match s {
             "" => DisplayHint::NoHint { zero_pad },
             "us" => DisplayHint::Seconds(TimePrecision::Micros),
@@ -100,6 +202,8 @@ impl DisplayHint {
             "tms" => DisplayHint::Time(TimePrecision::Millis),
             "ts" => DisplayHint::Time(TimePrecision::Seconds),
             "a" => DisplayHint::Ascii,
+            "e" => DisplayHint::Scientific { uppercase: false },
+            "E" => DisplayHint::Scientific { uppercase: true },
             "b" => DisplayHint::Binary {
                 alternate,
                 zero_pad,
@@ -121,12 +225,59 @@ impl DisplayHint {
             "iso8601ms" => DisplayHint::ISO8601(TimePrecision::Millis),
             "iso8601s" => DisplayHint::ISO8601(TimePrecision::Seconds),
             "cbor" => DisplayHint::Cbor,
+            "hexdump" => DisplayHint::Hexdump,
             "?" => DisplayHint::Debug,
             _ => return None,
         })
     }
 }
 
+/// `core::fmt`-like fill, alignment, width and precision of a parameter
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FormatSpec {
+    /// The character to pad the parameter with, a space by default
+    pub fill: char,
+    /// The alignment of the parameter within `width`; by default, numbers are aligned to the
+    /// right, and everything else to the left
+    pub align: Option<Alignment>,
+    /// The minimum width of the parameter, in characters
+    pub width: Option<usize>,
+    /// The number of digits after the decimal point of floats, or the maximum number of
+    /// characters of strings
+    pub precision: Option<usize>,
+}
+
+impl Default for FormatSpec {
+    fn default() -> Self {
+        Self {
+            fill: ' ',
+            align: None,
+            width: None,
+            precision: None,
+        }
+    }
+}
+
+/// Alignment of a parameter within its width
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Alignment {
+    /// `<`
+    Left,
+    /// `^`
+    Center,
+    /// `>`
+    Right,
+}
+
+fn parse_alignment(c: char) -> Option<Alignment> {
+    match c {
+        '<' => Some(Alignment::Left),
+        '^' => Some(Alignment::Center),
+        '>' => Some(Alignment::Right),
+        _ => None,
+    }
+}
+
 /// Precision of timestamp
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum TimePrecision {
@@ -149,3 +300,27 @@ fn parse_integer<T: FromStr>(s: &str) -> Option<(&str, T)> {
     let num = s[..start_digits].parse().ok()?;
     Some((&s[start_digits..], num))
 }
+
+/// Returns whether `s` is a unit like `V`, `mV` or `%`.
+fn is_unit(s: &str) -> bool {
+    const UNITS: &[&str] = &["%", "ppm", "dB", "°C", "rpm"];
+    const PREFIXED_UNITS: &[&str] = &[
+        "V", "A", "W", "Wh", "Ah", "Hz", "Ohm", "Ω", "F", "H", "J", "N", "Pa", "K", "g", "m", "lx",
+    ];
+    const PREFIXES: &[&str] = &["p", "n", "u", "µ", "m", "c", "k", "M", "G"];
+
+    UNITS.contains(&s)
+        || PREFIXED_UNITS
+            .iter()
+            .any(|unit| match s.strip_suffix(unit) {
+                Some(prefix) => prefix.is_empty() || PREFIXES.contains(&prefix),
+                None => false,
+            })
+}
+
+/// Returns whether `s` is a Rust identifier, like the name of an enum or of a crate.
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c == '_' || c.is_alphabetic())
+        && chars.all(|c| c == '_' || c.is_alphanumeric())
+}
@@ -16,7 +16,7 @@ mod types;
 use std::{borrow::Cow, ops::Range};
 
 pub use crate::{
-    display_hint::{DisplayHint, TimePrecision},
+    display_hint::{Alignment, DisplayHint, FormatSpec, TimePrecision},
     types::Type,
 };
 
@@ -37,6 +37,8 @@ pub enum Error {
     MalformedFormatString,
     #[error("unknown display hint: {0:?}")]
     UnknownDisplayHint(String),
+    #[error("precision is only supported for floats and strings, not for {0:?}")]
+    UnsupportedPrecision(Type),
     #[error("unexpected content `{0:?}` in format string")]
     UnexpectedContentInFormatString(String),
     #[error("unmatched `{{` in format string")]
@@ -49,6 +51,12 @@ pub enum Error {
     UnusedArgument(usize),
 }
 
+/// Separates the message of a log statement from its structured fields.
+///
+/// The log macros turn `info!("msg", key = value)` into the format string
+/// `"msg\u{1f}key={0=?}"`, with one separator in front of every field.
+pub const FIELD_SEPARATOR: char = '\u{1f}';
+
 /// A parameter of the form `{{0=Type:hint}}` in a format string.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Parameter {
@@ -251,6 +259,12 @@ fn parse_param(mut input: &str, mode: ParserMode) -> Result<Param, Error> {
             (None, ParserMode::Strict) => return Err(Error::UnknownDisplayHint(input.to_owned())),
             (None, ParserMode::ForwardsCompatible) => Some(DisplayHint::Unknown(input.to_owned())),
         };
+
+        if let Some(DisplayHint::Formatted { spec, .. }) = &hint {
+            if spec.precision.is_some() && !ty.supports_precision() && mode == ParserMode::Strict {
+                return Err(Error::UnsupportedPrecision(ty));
+            }
+        }
     } else if !input.is_empty() {
         return Err(Error::UnexpectedContentInFormatString(input.to_owned()));
     }
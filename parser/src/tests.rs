@@ -12,6 +12,8 @@ use super::*;
 #[case::two_param_index_hint("0:a", Some(0), Type::Format, Some(DisplayHint::Ascii))]
 #[case::two_param_type_hint("=[u8]:#04x", None, Type::U8Slice, Some(DisplayHint::Hexadecimal {alternate: true, uppercase: false, zero_pad: 4}))]
 #[case::all_param("1=u8:b", Some(1), Type::U8, Some(DisplayHint::Binary { alternate: false, zero_pad: 0}))]
+#[case::float_precision("=f32:.2", None, Type::F32, Some(formatted(' ', None, None, Some(2), DisplayHint::NoHint { zero_pad: 0 })))]
+#[case::str_width("=str:>10", None, Type::Str, Some(formatted(' ', Some(Alignment::Right), Some(10), None, DisplayHint::NoHint { zero_pad: 0 })))]
 fn all_parse_param_cases(
     #[case] input: &str,
     #[case] index: Option<usize>,
@@ -44,6 +46,24 @@ fn all_parse_param_cases(
 #[case(":iso8601ms", DisplayHint::ISO8601(TimePrecision::Millis))]
 #[case(":iso8601s", DisplayHint::ISO8601(TimePrecision::Seconds))]
 #[case(":?", DisplayHint::Debug)]
+#[case(":q15", DisplayHint::QFormat(15))]
+#[case(":fixed(3)", DisplayHint::Fixed(3))]
+#[case(":fixed(0)", DisplayHint::Fixed(0))]
+#[case(":mV", DisplayHint::Unit("mV".to_string()))]
+#[case(":Hz", DisplayHint::Unit("Hz".to_string()))]
+#[case(":µA", DisplayHint::Unit("µA".to_string()))]
+#[case(":%", DisplayHint::Unit("%".to_string()))]
+#[case(":10", formatted(' ', None, Some(10), None, DisplayHint::NoHint { zero_pad: 0 }))]
+#[case(":<8.3", formatted(' ', Some(Alignment::Left), Some(8), Some(3), DisplayHint::NoHint { zero_pad: 0 }))]
+#[case(":*^8", formatted('*', Some(Alignment::Center), Some(8), None, DisplayHint::NoHint { zero_pad: 0 }))]
+#[case(":>#6x", formatted(' ', Some(Alignment::Right), Some(6), None, DisplayHint::Hexadecimal { alternate: true, uppercase: false, zero_pad: 0 }))]
+#[case(":<<", formatted('<', Some(Alignment::Left), None, None, DisplayHint::NoHint { zero_pad: 0 }))]
+#[case(":>6mV", formatted(' ', Some(Alignment::Right), Some(6), None, DisplayHint::Unit("mV".to_string())))]
+#[case(":hexdump", DisplayHint::Hexdump)]
+#[case(":e", DisplayHint::Scientific { uppercase: false })]
+#[case(":.3E", formatted(' ', None, None, Some(3), DisplayHint::Scientific { uppercase: true }))]
+#[case(":enum(Mode)", DisplayHint::Enum { name: "Mode".to_string(), crate_name: None })]
+#[case(":enum(app::Mode)", DisplayHint::Enum { name: "Mode".to_string(), crate_name: Some("app".to_string()) })]
 #[case(":02", DisplayHint::NoHint { zero_pad: 2 })]
 fn all_display_hints(#[case] input: &str, #[case] hint: DisplayHint) {
     assert_eq!(
@@ -56,6 +76,24 @@ fn all_display_hints(#[case] input: &str, #[case] hint: DisplayHint) {
     );
 }
 
+fn formatted(
+    fill: char,
+    align: Option<Alignment>,
+    width: Option<usize>,
+    precision: Option<usize>,
+    hint: DisplayHint,
+) -> DisplayHint {
+    DisplayHint::Formatted {
+        spec: FormatSpec {
+            fill,
+            align,
+            width,
+            precision,
+        },
+        hint: Box::new(hint),
+    }
+}
+
 #[test]
 // separate test, because of `ParserMode::ForwardsCompatible`
 fn display_hint_unknown() {
@@ -191,6 +229,17 @@ fn arrays_err(#[case] input: &str) {
 #[case("{dunno=u8:x}", Error::UnexpectedContentInFormatString("dunno=u8:x".to_string()))]
 #[case("{0dunno}", Error::UnexpectedContentInFormatString("dunno".to_string()))]
 #[case("{:}", Error::MalformedFormatString)]
+#[case("{=f32:.}", Error::UnknownDisplayHint(".".to_string()))]
+#[case("{=str:>10zz}", Error::UnknownDisplayHint(">10zz".to_string()))]
+#[case("{=u8:>4.2}", Error::UnsupportedPrecision(Type::U8))]
+#[case("{=[u8]:.2}", Error::UnsupportedPrecision(Type::U8Slice))]
+#[case("{=u8:q0}", Error::UnknownDisplayHint("q0".to_string()))]
+#[case("{=u8:q65}", Error::UnknownDisplayHint("q65".to_string()))]
+#[case("{=u8:fixed(39)}", Error::UnknownDisplayHint("fixed(39)".to_string()))]
+#[case("{=u8:fixed()}", Error::UnknownDisplayHint("fixed()".to_string()))]
+#[case("{=u8:kdB}", Error::UnknownDisplayHint("kdB".to_string()))]
+#[case("{=u8:enum(a::b::Mode)}", Error::UnknownDisplayHint("enum(a::b::Mode)".to_string()))]
+#[case("{=u8:enum(::Mode)}", Error::UnknownDisplayHint("enum(::Mode)".to_string()))]
 #[case::stray_braces_1("}string", Error::UnmatchedCloseBracket)]
 #[case::stray_braces_2("{string", Error::UnmatchedOpenBracket)]
 #[case::stray_braces_3("}", Error::UnmatchedCloseBracket)]
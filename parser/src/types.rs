@@ -45,6 +45,35 @@ pub enum Type {
     U8Array(usize), // FIXME: This `usize` is not the target's `usize`; use `u64` instead?
 }
 
+impl Type {
+    /// Returns whether a precision, like in `{=f32:.2}`, applies to arguments of this type.
+    ///
+    /// It is the number of digits after the decimal point of floats, and the maximum length of
+    /// strings; other types like `Format` may contain those.
+    pub(crate) fn supports_precision(&self) -> bool {
+        !matches!(
+            self,
+            Type::BitField(_)
+                | Type::Bool
+                | Type::Char
+                | Type::I8
+                | Type::I16
+                | Type::I32
+                | Type::I64
+                | Type::I128
+                | Type::Isize
+                | Type::U8
+                | Type::U16
+                | Type::U32
+                | Type::U64
+                | Type::U128
+                | Type::Usize
+                | Type::U8Slice
+                | Type::U8Array(_)
+        )
+    }
+}
+
 // FIXME: either all or none of the type parsing should be done in here
 impl FromStr for Type {
     type Err = ();
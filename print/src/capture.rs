@@ -0,0 +1,216 @@
+//! Raw captures of the received data, which can be decoded again later.
+//!
+//! A capture starts with a header:
+//!
+//! - the magic bytes `DEFMTCAP`
+//! - the version of the format, currently `1`
+//! - flags; bit 0 is set if the records contain host timestamps
+//!
+//! followed by one record for each chunk of received data:
+//!
+//! - the length of the data, as `u32` (little endian)
+//! - if enabled, the time the data was received at, in nanoseconds since the Unix epoch, as `u64`
+//!   (little endian)
+//! - the data
+
+use std::{
+    fs::File as StdFile,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::bail;
+use tokio::{fs::File, io::AsyncReadExt};
+
+pub(crate) const MAGIC: &[u8; 8] = b"DEFMTCAP";
+const VERSION: u8 = 1;
+const FLAG_TIMESTAMPS: u8 = 1 << 0;
+
+/// Writes a capture.
+pub(crate) struct CaptureWriter {
+    file: BufWriter<StdFile>,
+    timestamps: bool,
+}
+
+impl CaptureWriter {
+    pub(crate) fn create(path: &Path, timestamps: bool) -> anyhow::Result<Self> {
+        let mut file = BufWriter::new(StdFile::create(path)?);
+        let flags = if timestamps { FLAG_TIMESTAMPS } else { 0 };
+        file.write_all(MAGIC)?;
+        file.write_all(&[VERSION, flags])?;
+        file.flush()?;
+        Ok(Self { file, timestamps })
+    }
+
+    /// Appends a record with `data`, which was just received.
+    pub(crate) fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.file.write_all(&(data.len() as u32).to_le_bytes())?;
+        if self.timestamps {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as u64;
+            self.file.write_all(&now.to_le_bytes())?;
+        }
+        self.file.write_all(data)?;
+        // don't lose the data if we get killed
+        self.file.flush()
+    }
+}
+
+/// Reads the data of a capture, without its headers.
+pub(crate) struct CaptureReader {
+    file: File,
+    timestamps: bool,
+    /// Bytes left in the current record
+    remaining: usize,
+}
+
+impl CaptureReader {
+    /// Reads the header of the capture from `file`, right after the magic bytes.
+    pub(crate) async fn new(mut file: File) -> anyhow::Result<Self> {
+        let version = file.read_u8().await?;
+        if version != VERSION {
+            bail!("unsupported capture version {version}");
+        }
+        let flags = file.read_u8().await?;
+        Ok(Self {
+            file,
+            timestamps: flags & FLAG_TIMESTAMPS != 0,
+            remaining: 0,
+        })
+    }
+
+    /// Reads data into `buf`, returning 0 at the end of the capture.
+    ///
+    /// A truncated last record, e.g. because `defmt-print` was killed while writing it, ends the
+    /// capture after the data it contains.
+    pub(crate) async fn read(&mut self, buf: &mut [u8]) -> anyhow::Result<usize> {
+        while self.remaining == 0 {
+            let mut len = [0; 4];
+            match self.file.read_exact(&mut len).await {
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(0),
+                Err(e) => return Err(e.into()),
+            }
+            if self.timestamps {
+                match self.file.read_u64_le().await {
+                    Ok(_) => {}
+                    Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(0),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            self.remaining = u32::from_le_bytes(len) as usize;
+        }
+
+        let len = buf.len().min(self.remaining);
+        let read = self.file.read(&mut buf[..len]).await?;
+        self.remaining -= read;
+        Ok(read)
+    }
+
+    /// Skips `n` bytes of data.
+    pub(crate) async fn skip(&mut self, mut n: u64) -> anyhow::Result<()> {
+        let mut buf = [0; 1024];
+        while n > 0 {
+            let len = buf.len().min(n.try_into().unwrap_or(usize::MAX));
+            match self.read(&mut buf[..len]).await? {
+                0 => break,
+                read => n -= read as u64,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    /// Returns a path for a capture file which is unique to the test `name`.
+    fn capture_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("defmt-print-{}-{name}.cap", std::process::id()))
+    }
+
+    async fn open(path: &Path) -> CaptureReader {
+        let mut file = File::open(path).await.unwrap();
+        let mut magic = [0; MAGIC.len()];
+        file.read_exact(&mut magic).await.unwrap();
+        assert_eq!(&magic, MAGIC);
+        CaptureReader::new(file).await.unwrap()
+    }
+
+    async fn read_to_end(reader: &mut CaptureReader) -> Vec<u8> {
+        let mut data = vec![];
+        let mut buf = [0; 3];
+        loop {
+            match reader.read(&mut buf).await.unwrap() {
+                0 => return data,
+                len => data.extend_from_slice(&buf[..len]),
+            }
+        }
+    }
+
+    fn write_capture(path: &Path, timestamps: bool, records: &[&[u8]]) {
+        let mut writer = CaptureWriter::create(path, timestamps).unwrap();
+        for record in records {
+            writer.write(record).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        for timestamps in [false, true] {
+            let path = capture_path(&format!("round-trip-{timestamps}"));
+            write_capture(&path, timestamps, &[b"hello", b"", b", world"]);
+
+            let mut reader = open(&path).await;
+            assert_eq!(reader.timestamps, timestamps);
+            assert_eq!(read_to_end(&mut reader).await, b"hello, world");
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn skip_across_records() {
+        let path = capture_path("skip");
+        write_capture(&path, true, &[b"abc", b"defg", b"hij"]);
+
+        let mut reader = open(&path).await;
+        reader.skip(5).await.unwrap();
+        assert_eq!(read_to_end(&mut reader).await, b"fghij");
+
+        // skipping past the end isn't an error
+        let mut reader = open(&path).await;
+        reader.skip(100).await.unwrap();
+        assert_eq!(read_to_end(&mut reader).await, b"");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn truncated_last_record() {
+        let path = capture_path("truncated");
+        write_capture(&path, true, &[b"hello", b", world"]);
+        let complete = std::fs::read(&path).unwrap();
+        // header, then a record of 4 + 8 + 5 bytes
+        let first_record_end = MAGIC.len() + 2 + 4 + 8 + 5;
+
+        // cut off in the length, the timestamp and the data of the last record
+        for (cut, expected) in [
+            (2, &b"hello"[..]),
+            (7, b"hello"),
+            (14, b"hello, "),
+            (17, b"hello, wor"),
+        ] {
+            std::fs::write(&path, &complete[..first_record_end + cut]).unwrap();
+            let mut reader = open(&path).await;
+            assert_eq!(read_to_end(&mut reader).await, expected, "cut at {cut}");
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
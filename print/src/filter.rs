@@ -0,0 +1,191 @@
+//! Host-side filtering of the decoded frames.
+
+use anyhow::anyhow;
+use defmt_decoder::{Frame, Level};
+use regex::Regex;
+
+/// Selects the frames to print, like `DEFMT_LOG` does on the target.
+///
+/// Printlns, which have no level, and the reports of the logger (dropped frames, fingerprint)
+/// are always printed.
+pub(crate) struct Filter {
+    pub(crate) min_level: Option<Level>,
+    /// Module path prefixes; frames from any of them are printed
+    pub(crate) modules: Vec<String>,
+    /// Frames whose message matches any of these are printed
+    pub(crate) grep: Vec<Regex>,
+    /// Frames whose message matches any of these are not printed
+    pub(crate) exclude: Vec<Regex>,
+}
+
+impl Filter {
+    /// Whether `frame`, logged from the module `module_path`, should be printed.
+    pub(crate) fn matches(&self, frame: &Frame, module_path: Option<&str>) -> bool {
+        let Some(level) = frame.level() else {
+            return true;
+        };
+        if frame.frames_dropped().is_some() || frame.fingerprint().is_some() {
+            return true;
+        }
+
+        if self.min_level.is_some_and(|min_level| level < min_level) {
+            return false;
+        }
+
+        if !self.modules.is_empty() {
+            let Some(module_path) = module_path else {
+                return false;
+            };
+            if !self
+                .modules
+                .iter()
+                .any(|prefix| is_in_module(module_path, prefix))
+            {
+                return false;
+            }
+        }
+
+        if self.grep.is_empty() && self.exclude.is_empty() {
+            return true;
+        }
+        let message = frame.display_message().to_string();
+        (self.grep.is_empty() || self.grep.iter().any(|re| re.is_match(&message)))
+            && !self.exclude.iter().any(|re| re.is_match(&message))
+    }
+}
+
+/// Whether `module_path` is the module `prefix` or one of its submodules.
+fn is_in_module(module_path: &str, prefix: &str) -> bool {
+    match module_path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with("::"),
+        None => false,
+    }
+}
+
+pub(crate) fn parse_level(s: &str) -> anyhow::Result<Level> {
+    match &*s.to_ascii_lowercase() {
+        "trace" => Ok(Level::Trace),
+        "debug" => Ok(Level::Debug),
+        "info" => Ok(Level::Info),
+        "warn" => Ok(Level::Warn),
+        "error" => Ok(Level::Error),
+        _ => Err(anyhow!(
+            "unknown log level `{s}`; expected trace, debug, info, warn or error"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use defmt_decoder::Table;
+
+    use super::*;
+
+    /// A table with a println and log statements of several levels.
+    fn table() -> Table {
+        let entry = |tag: &str, string: &str| {
+            format!(r#"{{"tag":"{tag}","string":"{string}","raw_symbol":""}}"#)
+        };
+        let json = format!(
+            r#"{{"defmt_table":1,"encoding":"raw","sequence_numbers":false,"frame_crc":false,
+            "timestamp":null,"bitflags":[],"locations":{{}},
+            "entries":{{"0":{},"1":{},"2":{},"3":{}}}}}"#,
+            entry("defmt_println", "hello"),
+            entry("defmt_debug", "radio packet received"),
+            entry("defmt_info", "radio ready"),
+            entry("defmt_warn", "battery low"),
+        );
+        Table::deserialize(json.as_bytes()).unwrap().0
+    }
+
+    fn filter() -> Filter {
+        Filter {
+            min_level: None,
+            modules: vec![],
+            grep: vec![],
+            exclude: vec![],
+        }
+    }
+
+    fn matches(filter: &Filter, bytes: &[u8], module_path: Option<&str>) -> bool {
+        let table = table();
+        let frame = table.decode(bytes).unwrap().0;
+        filter.matches(&frame, module_path)
+    }
+
+    const PRINTLN: &[u8] = &[0, 0];
+    const DEBUG: &[u8] = &[1, 0];
+    const INFO: &[u8] = &[2, 0];
+    const WARN: &[u8] = &[3, 0];
+    // 3 frames dropped
+    const DROPPED: &[u8] = &[0xff, 0xff, 3, 0, 0, 0];
+    const FINGERPRINT: &[u8] = &[0xfe, 0xff, 1, 0, 0, 0, 0, 0, 0, 0];
+
+    #[test]
+    fn min_level() {
+        let filter = Filter {
+            min_level: Some(Level::Info),
+            ..filter()
+        };
+        assert!(!matches(&filter, DEBUG, None));
+        assert!(matches(&filter, INFO, None));
+        assert!(matches(&filter, WARN, None));
+    }
+
+    #[test]
+    fn modules() {
+        let filter = Filter {
+            modules: vec!["app::radio".to_string()],
+            ..filter()
+        };
+        assert!(matches(&filter, INFO, Some("app::radio")));
+        assert!(matches(&filter, INFO, Some("app::radio::rx")));
+        assert!(!matches(&filter, INFO, Some("app::radio2")));
+        assert!(!matches(&filter, INFO, Some("app")));
+        assert!(!matches(&filter, INFO, None));
+    }
+
+    #[test]
+    fn submodules() {
+        assert!(is_in_module("app::radio", "app::radio"));
+        assert!(is_in_module("app::radio::rx", "app::radio"));
+        assert!(is_in_module("app::radio::rx", "app"));
+        assert!(!is_in_module("app::radio2", "app::radio"));
+        assert!(!is_in_module("app", "app::radio"));
+    }
+
+    #[test]
+    fn grep_and_exclude() {
+        let filter = Filter {
+            grep: vec![Regex::new("radio").unwrap()],
+            exclude: vec![Regex::new("packet").unwrap()],
+            ..filter()
+        };
+        assert!(matches(&filter, INFO, None));
+        assert!(!matches(&filter, DEBUG, None));
+        assert!(!matches(&filter, WARN, None));
+    }
+
+    #[test]
+    fn always_printed() {
+        let filter = Filter {
+            min_level: Some(Level::Error),
+            modules: vec!["app::radio".to_string()],
+            grep: vec![Regex::new("radio").unwrap()],
+            exclude: vec![Regex::new(".").unwrap()],
+        };
+        assert!(matches(&filter, PRINTLN, None));
+        assert!(matches(&filter, DROPPED, None));
+        assert!(matches(&filter, FINGERPRINT, None));
+        assert!(!matches(&filter, WARN, Some("app::radio")));
+    }
+
+    #[test]
+    fn level_names() {
+        assert_eq!(parse_level("warn").unwrap(), Level::Warn);
+        assert_eq!(parse_level("INFO").unwrap(), Level::Info);
+        assert_eq!(parse_level("Trace").unwrap(), Level::Trace);
+        assert!(parse_level("warning").is_err());
+        assert!(parse_level("").is_err());
+    }
+}
@@ -1,34 +1,50 @@
 use std::{
+    collections::BTreeSet,
     env,
+    io::SeekFrom,
     path::{Path, PathBuf},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
 use clap::{Parser, Subcommand};
 use defmt_decoder::{
     log::{
         format::{Formatter, FormatterConfig, HostFormatter},
         DefmtLoggerType,
     },
-    DecodeError, Frame, Locations, Table, DEFMT_VERSIONS,
+    DecodeError, Frame, Level, Locations, Table, DEFMT_VERSIONS,
 };
+use futures::future::try_join_all;
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use regex::Regex;
 use tokio::{
-    fs,
-    io::{self, AsyncReadExt, Stdin},
+    fs::{self, File},
+    io::{self, AsyncReadExt, AsyncSeekExt, Stdin},
     net::TcpStream,
     select,
     sync::mpsc::Receiver,
 };
 use tokio_serial::{SerialPort, SerialPortBuilderExt, SerialStream};
 
+use crate::{
+    capture::{CaptureReader, CaptureWriter},
+    filter::Filter,
+};
+
+mod capture;
+mod filter;
+
 /// Prints defmt-encoded logs to stdout
 #[derive(Parser, Clone)]
 #[command(name = "defmt-print")]
 struct Opts {
+    /// The ELF file of the firmware, or a table written by the `write-table` subcommand
+    ///
+    /// Repeat it to decode the logs of several firmware images at once, e.g. one for each core of
+    /// a multi-core chip. Each of them is paired with one of the sources, in order.
     #[arg(short, required = true, conflicts_with("version"))]
-    elf: Option<PathBuf>,
+    elf: Vec<PathBuf>,
 
     #[arg(long)]
     json: bool,
@@ -48,9 +64,44 @@ struct Opts {
     #[arg(short = 'V', long)]
     version: bool,
 
+    /// Reload the ELF file when it changes, e.g. when the firmware is rebuilt and re-flashed
     #[arg(short, long)]
     watch_elf: bool,
 
+    /// Exit with an error, instead of warning, if the fingerprint the firmware sends doesn't
+    /// match the ELF file
+    #[arg(long)]
+    strict_fingerprint: bool,
+
+    /// Keep timestamps like `{=u32:us}` increasing when they wrap around, by extending them to
+    /// 64 bits
+    #[arg(long)]
+    extend_timestamps: bool,
+
+    /// Only print the frames with at least this log level
+    #[arg(long, value_parser = filter::parse_level)]
+    min_level: Option<Level>,
+
+    /// Only print the frames logged from this module or its submodules; can be repeated
+    #[arg(long = "module", value_name = "PREFIX")]
+    modules: Vec<String>,
+
+    /// Only print the frames whose message matches this regex; can be repeated
+    #[arg(long, value_name = "REGEX")]
+    grep: Vec<Regex>,
+
+    /// Don't print the frames whose message matches this regex; can be repeated
+    #[arg(long, value_name = "REGEX")]
+    exclude: Vec<Regex>,
+
+    /// Also write the received data to this file, to decode it again later
+    #[arg(long)]
+    capture: Option<PathBuf>,
+
+    /// Record when the data was received in the capture
+    #[arg(long, requires = "capture")]
+    capture_timestamps: bool,
+
     #[command(subcommand)]
     command: Option<Command>,
 }
@@ -64,12 +115,14 @@ enum Command {
         #[arg(long, env = "RTT_HOST", default_value = "localhost")]
         host: String,
 
-        #[arg(long, env = "RTT_PORT", default_value_t = 19021)]
-        port: u16,
+        /// Repeat to read from several ports, one for each ELF file
+        #[arg(long, env = "RTT_PORT", default_values_t = [19021])]
+        port: Vec<u16>,
     },
     Serial {
+        /// Repeat to read from several serial ports, one for each ELF file
         #[arg(long, env = "SERIAL_PORT", default_value = "/dev/ttyUSB0")]
-        path: PathBuf,
+        path: Vec<PathBuf>,
 
         #[arg(long, env = "SERIAL_BAUD", default_value_t = 115200)]
         baud: u32,
@@ -77,12 +130,37 @@ enum Command {
         #[arg(long, env = "SERIAL_DTR", default_value_t = false)]
         dtr: bool,
     },
+    /// Read defmt frames from a file, e.g. a captured log
+    ///
+    /// Captures written with `--capture` are supported as well; offsets then refer to the captured
+    /// data, without the headers of the capture.
+    File {
+        /// Pass several files to decode them along with several ELF files, one for each
+        #[arg(required = true)]
+        path: Vec<PathBuf>,
+
+        /// Start decoding at this byte offset
+        #[arg(long, default_value_t = 0)]
+        offset: u64,
+
+        /// Skip the data up to the first frame boundary, e.g. when starting in the middle of a frame
+        #[arg(long)]
+        resync: bool,
+
+        /// Print the byte offset of each frame in front of it
+        #[arg(long)]
+        show_offsets: bool,
+    },
+    /// Write the defmt table of the ELF file to a standalone file, which `-e` accepts as well
+    WriteTable { path: PathBuf },
 }
 
 enum Source {
     Stdin(Stdin),
     Tcp(TcpStream),
     Serial(SerialStream),
+    File(File),
+    Capture(CaptureReader),
 }
 
 impl Source {
@@ -106,6 +184,26 @@ impl Source {
         Ok(Source::Serial(ser))
     }
 
+    async fn file(path: PathBuf, offset: u64) -> anyhow::Result<Self> {
+        let mut file = File::open(path).await?;
+
+        let mut magic = [0; capture::MAGIC.len()];
+        let is_capture = match file.read_exact(&mut magic).await {
+            Ok(_) => &magic == capture::MAGIC,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => false,
+            Err(e) => return Err(e.into()),
+        };
+
+        if is_capture {
+            let mut reader = CaptureReader::new(file).await?;
+            reader.skip(offset).await?;
+            Ok(Source::Capture(reader))
+        } else {
+            file.seek(SeekFrom::Start(offset)).await?;
+            Ok(Source::File(file))
+        }
+    }
+
     async fn read(&mut self, buf: &mut [u8]) -> anyhow::Result<(usize, bool)> {
         match self {
             Source::Stdin(stdin) => {
@@ -114,12 +212,24 @@ impl Source {
             }
             Source::Tcp(tcpstream) => Ok((tcpstream.read(buf).await?, false)),
             Source::Serial(serial) => Ok((serial.read(buf).await?, false)),
+            Source::File(file) => {
+                let n = file.read(buf).await?;
+                Ok((n, n == 0))
+            }
+            Source::Capture(reader) => {
+                let n = reader.read(buf).await?;
+                Ok((n, n == 0))
+            }
         }
     }
 }
 
 const READ_BUFFER_SIZE: usize = 1024;
 
+/// How long to skip the frames of the old firmware after a reload, waiting for the fingerprint of
+/// the new one; e.g. the rebuilt firmware may not have been flashed yet.
+const FINGERPRINT_TIMEOUT: Duration = Duration::from_secs(3);
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let opts = Opts::parse();
@@ -128,41 +238,100 @@ async fn main() -> anyhow::Result<()> {
         return print_version();
     }
 
-    // We create the source outside of the run command since recreating the stdin looses us some frames
-    let mut source = match opts.command.clone() {
-        None | Some(Command::Stdin) => Source::stdin(),
-        Some(Command::Tcp { host, port }) => Source::tcp(host, port).await?,
-        Some(Command::Serial { path, baud, dtr }) => Source::serial(path, baud, dtr)?,
+    if let Some(Command::WriteTable { path }) = &opts.command {
+        let [elf] = &opts.elf[..] else {
+            bail!("`write-table` takes a single ELF file");
+        };
+        let (table, locs) = load_table(elf).await?;
+        fs::write(path, table.serialize(&locs)).await?;
+        return Ok(());
+    }
+
+    // We create the sources outside of the run command since recreating the stdin looses us some frames
+    let mut sources = match opts.command.clone() {
+        None | Some(Command::Stdin) => vec![Source::stdin()],
+        Some(Command::WriteTable { .. }) => unreachable!(),
+        Some(Command::Tcp { host, port }) => {
+            let mut sources = vec![];
+            for port in port {
+                sources.push(Source::tcp(host.clone(), port).await?);
+            }
+            sources
+        }
+        Some(Command::Serial { path, baud, dtr }) => path
+            .into_iter()
+            .map(|path| Source::serial(path, baud, dtr))
+            .collect::<anyhow::Result<_>>()?,
+        Some(Command::File { path, offset, .. }) => {
+            let mut sources = vec![];
+            for path in path {
+                sources.push(Source::file(path, offset).await?);
+            }
+            sources
+        }
+    };
+
+    if sources.len() != opts.elf.len() {
+        bail!(
+            "the number of ELF files ({}) doesn't match the number of sources ({})",
+            opts.elf.len(),
+            sources.len()
+        );
+    }
+    if sources.len() > 1 && opts.capture.is_some() {
+        bail!("`--capture` only supports a single source");
+    }
+
+    let mut capture = match &opts.capture {
+        Some(path) => Some(CaptureWriter::create(path, opts.capture_timestamps)?),
+        None => None,
     };
 
     if opts.watch_elf {
-        run_and_watch(opts, &mut source).await
+        run_and_watch(opts, &mut sources, &mut capture).await
     } else {
-        run(opts, &mut source).await
+        run(opts, &mut sources, &mut capture, false).await
     }
 }
 
-async fn has_file_changed(rx: &mut Receiver<Result<Event, notify::Error>>, path: &PathBuf) -> bool {
+async fn has_file_changed(
+    rx: &mut Receiver<Result<Event, notify::Error>>,
+    paths: &[PathBuf],
+) -> bool {
     loop {
         if let Some(Ok(event)) = rx.recv().await {
-            if event.paths.contains(path) {
+            if event.paths.iter().any(|path| paths.contains(path)) {
                 if let notify::EventKind::Create(_) | notify::EventKind::Modify(_) = event.kind {
                     break;
                 }
             }
         }
     }
+
+    // the file is usually written in several steps; wait for the last one
+    while let Ok(Some(_)) = tokio::time::timeout(Duration::from_millis(200), rx.recv()).await {}
     true
 }
 
-async fn run_and_watch(opts: Opts, source: &mut Source) -> anyhow::Result<()> {
+async fn run_and_watch(
+    opts: Opts,
+    sources: &mut [Source],
+    capture: &mut Option<CaptureWriter>,
+) -> anyhow::Result<()> {
     let (tx, mut rx) = tokio::sync::mpsc::channel(1);
 
-    let path = opts.elf.clone().unwrap().canonicalize().unwrap();
+    let paths = opts
+        .elf
+        .iter()
+        .map(|elf| elf.canonicalize())
+        .collect::<Result<Vec<_>, _>>()?;
 
-    // We want the elf directory instead of the elf, since some editors remove
+    // We want the elf directories instead of the elfs, since some editors remove
     // and recreate the file on save which will remove the notifier
-    let directory_path = path.parent().unwrap();
+    let directory_paths = paths
+        .iter()
+        .map(|path| path.parent().unwrap())
+        .collect::<BTreeSet<_>>();
 
     let mut watcher = RecommendedWatcher::new(
         move |res| {
@@ -170,17 +339,43 @@ async fn run_and_watch(opts: Opts, source: &mut Source) -> anyhow::Result<()> {
         },
         Config::default(),
     )?;
-    watcher.watch(directory_path.as_ref(), RecursiveMode::NonRecursive)?;
+    for directory_path in directory_paths {
+        watcher.watch(directory_path, RecursiveMode::NonRecursive)?;
+    }
 
+    let mut reloaded = false;
     loop {
         select! {
-            r = run(opts.clone(), source) => r?,
-            _ = has_file_changed(&mut rx, &path) => ()
+            // the sources are exhausted
+            r = run(opts.clone(), sources, capture, reloaded) => return r,
+            _ = has_file_changed(&mut rx, &paths) => reloaded = true,
         }
     }
 }
 
-async fn run(opts: Opts, source: &mut Source) -> anyhow::Result<()> {
+/// Options for decoding the data of one source.
+#[derive(Clone, Copy)]
+struct DecodeOptions {
+    offset: u64,
+    resync: bool,
+    show_offsets: bool,
+    show_skipped_frames: bool,
+    strict_fingerprint: bool,
+    verbose: bool,
+    /// Whether the ELF files were just reloaded
+    reloaded: bool,
+}
+
+/// Decodes the data of the `sources` with the tables of the ELF files.
+///
+/// If `reloaded`, the ELF files changed since the last call, which was interrupted; the data
+/// then likely starts in the middle of a frame of the old firmware.
+async fn run(
+    opts: Opts,
+    sources: &mut [Source],
+    capture: &mut Option<CaptureWriter>,
+    reloaded: bool,
+) -> anyhow::Result<()> {
     let Opts {
         elf,
         json,
@@ -188,21 +383,61 @@ async fn run(opts: Opts, source: &mut Source) -> anyhow::Result<()> {
         host_log_format,
         show_skipped_frames,
         verbose,
+        strict_fingerprint,
+        extend_timestamps,
+        min_level,
+        modules,
+        grep,
+        exclude,
+        command,
         ..
     } = opts;
 
-    // read and parse elf file
-    let bytes = fs::read(elf.unwrap()).await?;
-    let table = Table::parse(&bytes)?.ok_or_else(|| anyhow!(".defmt data not found"))?;
-    let locs = table.get_locations(&bytes)?;
-
-    // check if the locations info contains all the indicies
-    let locs = if table.indices().all(|idx| locs.contains_key(&(idx as u64))) {
-        Some(locs)
-    } else {
-        log::warn!("(BUG) location info is incomplete; it will be omitted from the output");
-        None
+    let (offset, resync, show_offsets) = match command {
+        Some(Command::File {
+            offset,
+            resync,
+            show_offsets,
+            ..
+        }) => (offset, resync, show_offsets),
+        _ => (0, false, false),
     };
+    if show_offsets && json {
+        bail!("`--show-offsets` can't be combined with `--json`");
+    }
+
+    let mut tables = vec![];
+    for elf in &elf {
+        let (table, locs) = load_table(elf).await?;
+
+        // check if the locations info contains all the indicies
+        let locs = if table.indices().all(|idx| locs.contains_key(&(idx as u64))) {
+            Some(locs)
+        } else {
+            log::warn!("(BUG) location info is incomplete; it will be omitted from the output");
+            None
+        };
+
+        if resync && table.encoding().frame_separator().is_none() {
+            bail!(
+                "the {:?} encoding has no frame boundaries to resync to",
+                table.encoding()
+            );
+        }
+
+        tables.push((table, locs));
+    }
+
+    // with several firmware images, name the cores after their ELF files
+    let cores = elf
+        .iter()
+        .map(|path| {
+            (elf.len() > 1).then(|| {
+                let name = path.file_stem().unwrap_or(path.as_os_str());
+                name.to_string_lossy().into_owned()
+            })
+        })
+        .collect::<Vec<_>>();
 
     let logger_type = if json {
         DefmtLoggerType::Json
@@ -219,7 +454,13 @@ async fn run(opts: Opts, source: &mut Source) -> anyhow::Result<()> {
         FormatterConfig::default()
     };
 
-    formatter_config.is_timestamp_available = table.has_timestamp();
+    formatter_config.is_timestamp_available = tables.iter().any(|(table, _)| table.has_timestamp());
+    if elf.len() > 1 {
+        formatter_config = formatter_config.with_core();
+    }
+    if extend_timestamps {
+        formatter_config = formatter_config.with_extended_timestamps();
+    }
 
     let cloned_host_format = host_log_format.clone().unwrap_or_default();
     let host_formatter_config = if host_log_format.is_some() {
@@ -240,9 +481,84 @@ async fn run(opts: Opts, source: &mut Source) -> anyhow::Result<()> {
         }
     });
 
+    if reloaded {
+        println!("(HOST) firmware reloaded");
+    }
+
+    let options = DecodeOptions {
+        offset,
+        resync,
+        show_offsets,
+        show_skipped_frames: show_skipped_frames || verbose,
+        strict_fingerprint,
+        verbose,
+        reloaded,
+    };
+    let filter = Filter {
+        min_level,
+        modules,
+        grep,
+        exclude,
+    };
+    // there is only a capture if there is a single source
+    let mut capture = capture.as_mut();
+    let decoders = sources
+        .iter_mut()
+        .zip(&tables)
+        .zip(&cores)
+        .map(|((source, (table, locs)), core)| {
+            decode(
+                source,
+                table,
+                locs,
+                core.as_deref(),
+                capture.take(),
+                options,
+                &filter,
+            )
+        })
+        .collect::<Vec<_>>();
+    // runs until all sources are exhausted, or one of them fails
+    try_join_all(decoders).await?;
+    Ok(())
+}
+
+/// Decodes the data of `source` and forwards the frames to the logger.
+async fn decode(
+    source: &mut Source,
+    table: &Table,
+    locs: &Option<Locations>,
+    core: Option<&str>,
+    mut capture: Option<&mut CaptureWriter>,
+    options: DecodeOptions,
+    filter: &Filter,
+) -> anyhow::Result<()> {
+    let DecodeOptions {
+        offset,
+        mut resync,
+        show_offsets,
+        show_skipped_frames,
+        strict_fingerprint,
+        verbose,
+        reloaded,
+    } = options;
+
+    let separator = table.encoding().frame_separator();
+    // after a reload, drop the rest of the frame that was being received
+    resync |= reloaded && separator.is_some();
+    // the new firmware sends its fingerprint first, if it has one; the frames before that are
+    // from the old firmware
+    let mut awaited_fingerprint = table.fingerprint().filter(|_| reloaded);
+    if awaited_fingerprint.is_some() {
+        println!("(HOST) waiting for firmware fingerprint…");
+    }
+    let wait_start = Instant::now();
+    let mut old_frames = 0;
     let mut buf = [0; READ_BUFFER_SIZE];
     let mut stream_decoder = table.new_stream_decoder();
     let current_dir = env::current_dir()?;
+    // offset of the end of the data read so far
+    let mut end_offset = offset;
 
     loop {
         // read from stdin or tcpstream and push it to the decoder
@@ -253,12 +569,75 @@ async fn run(opts: Opts, source: &mut Source) -> anyhow::Result<()> {
             break Ok(());
         }
 
-        stream_decoder.received(&buf[..n]);
+        if let Some(capture) = &mut capture {
+            capture.write(&buf[..n])?;
+        }
+
+        let mut data = &buf[..n];
+        end_offset += n as u64;
+        if resync {
+            // drop everything up to and including the first frame separator
+            match data.iter().position(|&x| Some(x) == separator) {
+                Some(end) => {
+                    data = &data[end + 1..];
+                    resync = false;
+                }
+                None => continue,
+            }
+        }
+
+        stream_decoder.received(data);
 
         // decode the received data
         loop {
+            let frame_offset = stream_decoder
+                .pending()
+                .map(|pending| end_offset - pending as u64);
             match stream_decoder.decode() {
-                Ok(frame) => forward_to_logger(&frame, location_info(&locs, &frame, &current_dir)),
+                Ok(frame) => {
+                    if let Some(fingerprint) = awaited_fingerprint {
+                        let received = frame.fingerprint() == Some(fingerprint);
+                        if !received && wait_start.elapsed() < FINGERPRINT_TIMEOUT {
+                            old_frames += 1;
+                            continue;
+                        }
+                        awaited_fingerprint = None;
+                        if old_frames > 0 {
+                            println!("(HOST) {old_frames} frames of the old firmware skipped");
+                        }
+                        if !received {
+                            println!("(HOST) no firmware fingerprint received; decoding the frames anyway");
+                        }
+                    }
+                    if frame.frames_corrupted() > 0 {
+                        println!(
+                            "(HOST) {} corrupted frames dropped",
+                            frame.frames_corrupted()
+                        );
+                    }
+                    if frame.frames_lost() > 0 {
+                        println!("(HOST) {} frames lost", frame.frames_lost());
+                    }
+                    if let Some(fingerprint) = frame.fingerprint() {
+                        match table.fingerprint() == Some(fingerprint) {
+                            // the firmware matches the ELF file; nothing to report
+                            true if !verbose => continue,
+                            false if strict_fingerprint => bail!(
+                                "the fingerprint of the firmware, {fingerprint:#x}, doesn't match the ELF file"
+                            ),
+                            _ => {}
+                        }
+                    }
+                    let location_info = location_info(locs, &frame, &current_dir);
+                    if !filter.matches(&frame, location_info.2.as_deref()) {
+                        observe(&frame, core);
+                        continue;
+                    }
+                    if let (true, Some(frame_offset)) = (show_offsets, frame_offset) {
+                        print!("{frame_offset:#010x} ");
+                    }
+                    forward_to_logger(&frame, location_info, core)
+                }
                 Err(DecodeError::UnexpectedEof) => break,
                 Err(DecodeError::Malformed) => match table.encoding().can_recover() {
                     // if recovery is impossible, abort
@@ -267,7 +646,7 @@ async fn run(opts: Opts, source: &mut Source) -> anyhow::Result<()> {
                     true => {
                         // bug: https://github.com/rust-lang/rust-clippy/issues/9810
                         #[allow(clippy::print_literal)]
-                        if show_skipped_frames || verbose {
+                        if show_skipped_frames {
                             println!("(HOST) malformed frame skipped");
                             println!("└─ {} @ {}:{}", env!("CARGO_PKG_NAME"), file!(), line!());
                         }
@@ -279,11 +658,41 @@ async fn run(opts: Opts, source: &mut Source) -> anyhow::Result<()> {
     }
 }
 
+/// Reads the table and locations from an ELF file, or from a table written by `write-table`.
+async fn load_table(path: &Path) -> anyhow::Result<(Table, Locations)> {
+    let bytes = fs::read(path).await?;
+    if !bytes.starts_with(b"\x7fELF") {
+        return Table::deserialize(&bytes);
+    }
+
+    // parse elf file
+    let table = Table::parse(&bytes)?.ok_or_else(|| anyhow!(".defmt data not found"))?;
+    let locs = table.get_locations(&bytes)?;
+    Ok((table, locs))
+}
+
 type LocationInfo = (Option<String>, Option<u32>, Option<String>);
 
-fn forward_to_logger(frame: &Frame, location_info: LocationInfo) {
+fn forward_to_logger(frame: &Frame, location_info: LocationInfo, core: Option<&str>) {
     let (file, line, mod_path) = location_info;
-    defmt_decoder::log::log_defmt(frame, file.as_deref(), line, mod_path.as_deref());
+    match core {
+        Some(core) => defmt_decoder::log::log_defmt_from_core(
+            frame,
+            core,
+            file.as_deref(),
+            line,
+            mod_path.as_deref(),
+        ),
+        None => defmt_decoder::log::log_defmt(frame, file.as_deref(), line, mod_path.as_deref()),
+    }
+}
+
+/// Lets the logger track the timestamp of a frame which is filtered out, for `{dt}`.
+fn observe(frame: &Frame, core: Option<&str>) {
+    match core {
+        Some(core) => defmt_decoder::log::observe_defmt_from_core(frame, core),
+        None => defmt_decoder::log::observe_defmt(frame),
+    }
 }
 
 fn location_info(locs: &Option<Locations>, frame: &Frame, current_dir: &Path) -> LocationInfo {
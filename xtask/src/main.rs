@@ -131,6 +131,26 @@ fn test_host(deny_warnings: bool, skip_ui_tests: bool) {
             "host",
         );
     }
+
+    // the integration tests expect the default encoding; the unit tests cover the varints
+    do_test(
+        || {
+            run_command(
+                "cargo",
+                &[
+                    "test",
+                    "-p",
+                    "defmt",
+                    "--lib",
+                    "--features",
+                    "unstable-test,encoding-varint",
+                ],
+                None,
+                &env,
+            )
+        },
+        "host",
+    );
 }
 
 fn test_cross(deny_warnings: bool) {